@@ -0,0 +1,644 @@
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::retry::{http_client, RetryPolicy};
+use super::signature::{self, Params};
+use super::{AssetMetadata, CloudinaryError};
+use crate::config::{CloudinaryConfig, DeliveryType, ResourceType, RetryConfig};
+
+/// Largest page the Admin API returns for resource listings
+const MAX_PAGE_SIZE: usize = 500;
+
+/// Most public IDs accepted by a single delete request
+pub const DELETE_BATCH_SIZE: usize = 100;
+
+/// Most public IDs accepted by a single tags request
+pub const TAG_BATCH_SIZE: usize = 1000;
+
+/// Client for the Admin API, authenticated with the API key and secret
+#[derive(Clone)]
+pub struct AdminApi {
+    config: CloudinaryConfig,
+    retry_policy: RetryPolicy,
+    client: reqwest::Client,
+    progress: Option<ProgressBar>,
+}
+
+/// An asset as described by the Admin API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Resource {
+    pub public_id: String,
+    #[serde(default)]
+    pub format: Option<String>,
+    pub resource_type: String,
+    #[serde(rename = "type")]
+    pub delivery_type: String,
+    #[serde(default)]
+    pub version: u64,
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub secure_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+/// Everything the Admin API reports about a single resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceDetails {
+    #[serde(flatten)]
+    pub resource: Resource,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub asset_folder: Option<String>,
+    #[serde(default)]
+    pub access_mode: Option<String>,
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub context: Option<serde_json::Value>,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    /// Embedded metadata such as EXIF, IPTC and XMP fields
+    #[serde(default)]
+    pub image_metadata: Option<BTreeMap<String, serde_json::Value>>,
+    /// Predominant colors with their share of the image in percent
+    #[serde(default)]
+    pub colors: Vec<(String, f64)>,
+    /// Detected faces as `[x, y, width, height]`
+    #[serde(default)]
+    pub faces: Vec<Vec<i64>>,
+    #[serde(default)]
+    pub derived: Vec<DerivedResource>,
+    /// Backed up versions, when backups are enabled
+    #[serde(default)]
+    pub versions: Vec<ResourceVersion>,
+}
+
+/// A transformed copy of a resource stored by Cloudinary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivedResource {
+    pub transformation: String,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default)]
+    pub secure_url: Option<String>,
+}
+
+/// A backed up version of a resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceVersion {
+    pub version_id: String,
+    #[serde(default)]
+    pub version: Option<u64>,
+    #[serde(default)]
+    pub format: Option<String>,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub time: Option<String>,
+    #[serde(default)]
+    pub restorable: Option<bool>,
+}
+
+impl AssetMetadata for ResourceDetails {
+    fn context(&self) -> Option<&serde_json::Value> {
+        self.context.as_ref()
+    }
+
+    fn metadata(&self) -> Option<&serde_json::Value> {
+        self.metadata.as_ref()
+    }
+}
+
+/// Which resources to list
+#[derive(Debug, Clone)]
+pub struct ResourceQuery {
+    pub resource_type: ResourceType,
+    pub delivery_type: DeliveryType,
+    /// Only public IDs starting with this prefix
+    pub prefix: Option<String>,
+    /// Only resources with this tag; cannot be combined with a prefix
+    pub tag: Option<String>,
+    /// Stop after this many resources
+    pub max: Option<usize>,
+}
+
+/// One page of a listing, with the cursor of the next page if there is one
+#[derive(Debug, Deserialize)]
+struct ResourcePage {
+    resources: Vec<Resource>,
+    next_cursor: Option<String>,
+}
+
+/// A folder in the account's media library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Folder {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct FolderPage {
+    folders: Vec<Folder>,
+    next_cursor: Option<String>,
+}
+
+/// How a tags request changes the tags of its resources
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagOperation {
+    Add,
+    Remove,
+    /// Replace all existing tags with the given one
+    Replace,
+    /// Remove every tag
+    RemoveAll,
+}
+
+impl TagOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TagOperation::Add => "add",
+            TagOperation::Remove => "remove",
+            TagOperation::Replace => "replace",
+            TagOperation::RemoveAll => "remove_all",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    public_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteResponse {
+    deleted: BTreeMap<String, String>,
+}
+
+impl AdminApi {
+    pub fn new(config: CloudinaryConfig, retry: &RetryConfig) -> Result<Self> {
+        if config.api_key.is_empty() || config.api_secret.is_empty() {
+            anyhow::bail!("The Admin API requires api_key and api_secret in the config");
+        }
+
+        Ok(AdminApi {
+            retry_policy: RetryPolicy::from_config(retry),
+            config,
+            client: http_client(retry),
+            progress: None,
+        })
+    }
+
+    /// Report retried requests on the given progress bar
+    pub fn with_progress(mut self, progress: ProgressBar) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// List resources matching `query`, following `next_cursor` until the
+    /// listing or `query.max` is exhausted
+    pub async fn list_resources(&self, query: &ResourceQuery) -> Result<Vec<Resource>> {
+        let resource_type = query.resource_type.as_str();
+        let path = match query.tag.as_deref() {
+            Some(tag) => vec!["resources", resource_type, "tags", tag],
+            None => vec!["resources", resource_type, query.delivery_type.as_str()],
+        };
+
+        let mut resources = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let page_size = match query.max {
+                Some(max) => (max - resources.len()).min(MAX_PAGE_SIZE),
+                None => MAX_PAGE_SIZE,
+            };
+
+            let mut params = vec![
+                ("max_results", page_size.to_string()),
+                ("tags", "true".to_string()),
+            ];
+            if let Some(prefix) = query.prefix.as_deref().filter(|_| query.tag.is_none()) {
+                params.push(("prefix", prefix.to_string()));
+            }
+            if let Some(cursor) = &cursor {
+                params.push(("next_cursor", cursor.clone()));
+            }
+
+            let page: ResourcePage = self.get(&path, &params).await?;
+            resources.extend(page.resources);
+
+            cursor = page.next_cursor;
+            let full = query.max.map_or(false, |max| resources.len() >= max);
+            if cursor.is_none() || full {
+                break;
+            }
+        }
+
+        // A page may hold more than was asked for
+        if let Some(max) = query.max {
+            resources.truncate(max);
+        }
+
+        Ok(resources)
+    }
+
+    /// Delete up to [`DELETE_BATCH_SIZE`] resources by public ID, returning the
+    /// outcome for each ID (`deleted` or `not_found`)
+    pub async fn delete_resources(
+        &self,
+        resource_type: ResourceType,
+        delivery_type: DeliveryType,
+        public_ids: &[String],
+        invalidate: bool,
+    ) -> Result<BTreeMap<String, String>> {
+        let path = ["resources", resource_type.as_str(), delivery_type.as_str()];
+
+        let mut params: Vec<(&str, String)> = public_ids
+            .iter()
+            .map(|id| ("public_ids[]", id.clone()))
+            .collect();
+        if invalidate {
+            params.push(("invalidate", "true".to_string()));
+        }
+
+        let response: DeleteResponse = self.request(Method::DELETE, &path, &params).await?;
+        Ok(response.deleted)
+    }
+
+    /// Details of a single resource, including colors, faces, embedded
+    /// metadata and backed up versions
+    pub async fn resource(
+        &self,
+        resource_type: ResourceType,
+        delivery_type: DeliveryType,
+        public_id: &str,
+    ) -> Result<ResourceDetails> {
+        // Slashes in the public ID are path separators, not part of a segment
+        let mut path = vec!["resources", resource_type.as_str(), delivery_type.as_str()];
+        path.extend(public_id.split('/'));
+
+        let params = ["colors", "faces", "image_metadata", "versions"]
+            .map(|name| (name, "true".to_string()));
+        self.get(&path, &params).await
+    }
+
+    /// Change the public ID of a resource, which also moves it between folders
+    /// in fixed folder mode
+    pub async fn rename(
+        &self,
+        resource_type: ResourceType,
+        delivery_type: DeliveryType,
+        from_public_id: &str,
+        to_public_id: &str,
+        overwrite: bool,
+        invalidate: bool,
+    ) -> Result<Resource> {
+        let mut params = Params::new();
+        params.insert("from_public_id".to_string(), from_public_id.into());
+        params.insert("to_public_id".to_string(), to_public_id.into());
+        params.insert("type".to_string(), delivery_type.as_str().into());
+        if overwrite {
+            params.insert("overwrite".to_string(), "true".into());
+        }
+        if invalidate {
+            params.insert("invalidate".to_string(), "true".into());
+        }
+
+        self.post_signed(&[resource_type.as_str(), "rename"], params)
+            .await
+    }
+
+    /// Apply a tag operation to up to [`TAG_BATCH_SIZE`] resources, returning
+    /// the public IDs that were updated
+    pub async fn tags(
+        &self,
+        operation: TagOperation,
+        tag: Option<&str>,
+        resource_type: ResourceType,
+        delivery_type: DeliveryType,
+        public_ids: &[String],
+    ) -> Result<Vec<String>> {
+        let mut params = Params::new();
+        params.insert("command".to_string(), operation.as_str().into());
+        if let Some(tag) = tag {
+            params.insert("tag".to_string(), tag.into());
+        }
+        params.insert("type".to_string(), delivery_type.as_str().into());
+        params.insert("public_ids".to_string(), public_ids.to_vec().into());
+
+        let response: TagsResponse = self
+            .post_signed(&[resource_type.as_str(), "tags"], params)
+            .await?;
+        Ok(response.public_ids)
+    }
+
+    /// Folders directly below `path`, or the root folders when `path` is empty
+    pub async fn subfolders(&self, path: &str) -> Result<Vec<Folder>> {
+        let mut segments = vec!["folders"];
+        segments.extend(folder_segments(path));
+
+        let mut folders = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut params = vec![("max_results", MAX_PAGE_SIZE.to_string())];
+            if let Some(cursor) = &cursor {
+                params.push(("next_cursor", cursor.clone()));
+            }
+
+            let page: FolderPage = self.get(&segments, &params).await?;
+            folders.extend(page.folders);
+
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        Ok(folders)
+    }
+
+    /// Whether a folder exists, checked against the folders of its parent
+    pub async fn folder_exists(&self, path: &str) -> Result<bool> {
+        let path = path.trim_matches('/');
+        let parent = path
+            .rsplit_once('/')
+            .map(|(parent, _)| parent)
+            .unwrap_or_default();
+
+        match self.subfolders(parent).await {
+            Ok(folders) => Ok(folders.iter().any(|folder| folder.path == path)),
+            Err(err) if is_not_found(&err) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Create a folder along with any missing parent folders
+    pub async fn create_folder(&self, path: &str) -> Result<()> {
+        let mut segments = vec!["folders"];
+        segments.extend(folder_segments(path));
+
+        let _: serde_json::Value = self.request(Method::POST, &segments, &[]).await?;
+        Ok(())
+    }
+
+    /// Delete an empty folder
+    pub async fn delete_folder(&self, path: &str) -> Result<()> {
+        let mut segments = vec!["folders"];
+        segments.extend(folder_segments(path));
+
+        let _: serde_json::Value = self.request(Method::DELETE, &segments, &[]).await?;
+        Ok(())
+    }
+
+    async fn get<T>(&self, path: &[&str], params: &[(&str, String)]) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        self.request(Method::GET, path, params).await
+    }
+
+    /// Call an Admin API path relative to the cloud, decoding the JSON response
+    async fn request<T>(
+        &self,
+        method: Method,
+        path: &[&str],
+        params: &[(&str, String)],
+    ) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let url = self.endpoint(path)?;
+        let label = path.join("/");
+        let response = self
+            .retry_policy
+            .send(self.progress.as_ref(), &label, || async {
+                Ok(self
+                    .client
+                    .request(method.clone(), url.clone())
+                    .basic_auth(&self.config.api_key, Some(&self.config.api_secret))
+                    .query(params))
+            })
+            .await?;
+
+        response
+            .json::<T>()
+            .await
+            .with_context(|| format!("Failed to parse response from {}", label))
+    }
+
+    /// POST a signed Upload API request, used by the few management operations
+    /// that are not part of the Admin API
+    async fn post_signed<T>(&self, path: &[&str], params: Params) -> Result<T>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        let url = self.endpoint(path)?;
+        let label = path.join("/");
+        let fields = signature::signed_fields(params, &self.config)?;
+
+        let response = self
+            .retry_policy
+            .send(self.progress.as_ref(), &label, || async {
+                Ok(self.client.post(url.clone()).form(&fields))
+            })
+            .await?;
+
+        response
+            .json::<T>()
+            .await
+            .with_context(|| format!("Failed to parse response from {}", label))
+    }
+
+    /// URL of an API path below the cloud, with every segment percent-encoded
+    fn endpoint(&self, path: &[&str]) -> Result<url::Url> {
+        let api_url = self.config.api_url();
+        let mut url =
+            url::Url::parse(&api_url).with_context(|| format!("Invalid API URL: {}", api_url))?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("Invalid API URL: {}", api_url))?
+            .pop_if_empty()
+            .push(&self.config.cloud_name)
+            .extend(path);
+        Ok(url)
+    }
+}
+
+/// Path segments of a folder path, ignoring leading, trailing and doubled slashes
+fn folder_segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<CloudinaryError>(),
+            Some(CloudinaryError::NotFound(_))
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cloudinary::tests::{serve, ReceivedRequest};
+    use tokio::sync::mpsc::UnboundedReceiver;
+
+    async fn admin(responses: Vec<serde_json::Value>) -> (AdminApi, UnboundedReceiver<ReceivedRequest>) {
+        let (base_url, requests) = serve(responses.iter().map(ToString::to_string).collect()).await;
+        let config = CloudinaryConfig {
+            cloud_name: "demo".to_string(),
+            api_key: "key".to_string(),
+            api_secret: "secret".to_string(),
+            api_base_url: Some(base_url),
+            ..Default::default()
+        };
+        (AdminApi::new(config, &RetryConfig::default()).unwrap(), requests)
+    }
+
+    fn page(public_ids: &[&str], next_cursor: Option<&str>) -> serde_json::Value {
+        let resources: Vec<_> = public_ids
+            .iter()
+            .map(|id| serde_json::json!({ "public_id": id, "resource_type": "image", "type": "upload" }))
+            .collect();
+        serde_json::json!({ "resources": resources, "next_cursor": next_cursor })
+    }
+
+    fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    fn query(max: Option<usize>) -> ResourceQuery {
+        ResourceQuery {
+            resource_type: ResourceType::Image,
+            delivery_type: DeliveryType::Upload,
+            prefix: Some("blog/".to_string()),
+            tag: None,
+            max,
+        }
+    }
+
+    fn public_ids(resources: &[Resource]) -> Vec<&str> {
+        resources.iter().map(|resource| resource.public_id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn follows_next_cursor_until_the_last_page() {
+        let (admin, mut requests) =
+            admin(vec![page(&["a", "b"], Some("cursor-1")), page(&["c"], None)]).await;
+
+        let resources = admin.list_resources(&query(None)).await.unwrap();
+        assert_eq!(public_ids(&resources), ["a", "b", "c"]);
+
+        let first = requests.recv().await.unwrap();
+        assert_eq!(first.method, "GET");
+        assert_eq!(first.path(), "/demo/resources/image/upload");
+        assert!(first.headers["authorization"].starts_with("Basic "));
+        let params = first.query();
+        assert_eq!(param(&params, "max_results"), Some("500"));
+        assert_eq!(param(&params, "prefix"), Some("blog/"));
+        assert_eq!(param(&params, "next_cursor"), None);
+
+        let params = requests.recv().await.unwrap().query();
+        assert_eq!(param(&params, "next_cursor"), Some("cursor-1"));
+    }
+
+    #[tokio::test]
+    async fn stops_at_max_within_a_page() {
+        let (admin, mut requests) = admin(vec![
+            page(&["a", "b"], Some("cursor-1")),
+            page(&["c", "d"], Some("cursor-2")),
+        ])
+        .await;
+
+        let resources = admin.list_resources(&query(Some(3))).await.unwrap();
+        assert_eq!(public_ids(&resources), ["a", "b", "c"]);
+
+        let params = requests.recv().await.unwrap().query();
+        assert_eq!(param(&params, "max_results"), Some("3"));
+        let params = requests.recv().await.unwrap().query();
+        assert_eq!(param(&params, "max_results"), Some("1"));
+        assert_eq!(param(&params, "next_cursor"), Some("cursor-1"));
+
+        // cursor-2 is not followed
+        assert!(requests.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn lists_by_tag_through_the_tags_endpoint() {
+        let (admin, mut requests) = admin(vec![page(&["a"], None)]).await;
+
+        let query = ResourceQuery {
+            resource_type: ResourceType::Video,
+            tag: Some("summer 24".to_string()),
+            ..query(None)
+        };
+        admin.list_resources(&query).await.unwrap();
+
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request.path(), "/demo/resources/video/tags/summer%2024");
+        assert_eq!(param(&request.query(), "prefix"), None);
+    }
+
+    #[tokio::test]
+    async fn deletes_by_public_id() {
+        let response = serde_json::json!({ "deleted": { "a": "deleted", "b/c": "not_found" } });
+        let (admin, mut requests) = admin(vec![response]).await;
+
+        let ids = vec!["a".to_string(), "b/c".to_string()];
+        let deleted = admin
+            .delete_resources(ResourceType::Raw, DeliveryType::Private, &ids, true)
+            .await
+            .unwrap();
+        assert_eq!(deleted["a"], "deleted");
+        assert_eq!(deleted["b/c"], "not_found");
+
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request.method, "DELETE");
+        assert_eq!(request.path(), "/demo/resources/raw/private");
+        let params = request.query();
+        let sent: Vec<&str> = params
+            .iter()
+            .filter(|(key, _)| key == "public_ids[]")
+            .map(|(_, value)| value.as_str())
+            .collect();
+        assert_eq!(sent, ["a", "b/c"]);
+        assert_eq!(param(&params, "invalidate"), Some("true"));
+    }
+
+    #[tokio::test]
+    async fn changes_tags_with_a_signed_request() {
+        let (admin, mut requests) = admin(vec![serde_json::json!({ "public_ids": ["a", "b"] })]).await;
+
+        let ids = vec!["a".to_string(), "b".to_string()];
+        let updated = admin
+            .tags(TagOperation::Add, Some("hero"), ResourceType::Image, DeliveryType::Upload, &ids)
+            .await
+            .unwrap();
+        assert_eq!(updated, ["a", "b"]);
+
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path(), "/demo/image/tags");
+        let form = request.form();
+        assert_eq!(param(&form, "command"), Some("add"));
+        assert_eq!(param(&form, "tag"), Some("hero"));
+        assert_eq!(param(&form, "api_key"), Some("key"));
+        assert!(param(&form, "signature").is_some());
+        assert_eq!(form.iter().filter(|(key, _)| key == "public_ids[]").count(), 2);
+    }
+}
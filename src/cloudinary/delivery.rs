@@ -0,0 +1,337 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::URL_SAFE;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use super::Transformation;
+use crate::config::{CloudinaryConfig, DeliveryType, SignatureAlgorithm};
+
+/// Query parameter name Cloudinary's CDN expects for authentication tokens
+const TOKEN_NAME: &str = "__cld_token__";
+
+/// A delivery URL for a stored asset, built offline from the configuration.
+///
+/// URLs can be signed (`s--XXXXXXXX--`) with the API secret, as private and
+/// authenticated assets require, or carry a time-limited authentication token.
+pub struct DeliveryUrl<'a> {
+    config: &'a CloudinaryConfig,
+    public_id: &'a str,
+    resource_type: &'a str,
+    delivery_type: DeliveryType,
+    transformation: Option<&'a Transformation>,
+    version: Option<u64>,
+    signed: bool,
+    token: Option<AuthToken>,
+}
+
+/// Parameters of a token-based authentication URL
+#[derive(Debug, Clone)]
+pub struct AuthToken {
+    /// Unix time at which the token expires
+    pub expiration: u64,
+    /// Path pattern the token grants access to, e.g. `/image/authenticated/*`;
+    /// without one the token is only valid for the exact URL
+    pub acl: Option<String>,
+}
+
+impl<'a> DeliveryUrl<'a> {
+    pub fn new(config: &'a CloudinaryConfig, public_id: &'a str) -> Self {
+        DeliveryUrl {
+            config,
+            public_id,
+            resource_type: "image",
+            delivery_type: DeliveryType::Upload,
+            transformation: None,
+            version: None,
+            signed: false,
+            token: None,
+        }
+    }
+
+    pub fn resource_type(mut self, resource_type: &'a str) -> Self {
+        self.resource_type = resource_type;
+        self
+    }
+
+    /// Set the delivery type; private and authenticated URLs are signed by default
+    pub fn delivery_type(mut self, delivery_type: DeliveryType) -> Self {
+        self.delivery_type = delivery_type;
+        self.signed = delivery_type != DeliveryType::Upload;
+        self
+    }
+
+    pub fn transformation(mut self, transformation: Option<&'a Transformation>) -> Self {
+        self.transformation = transformation;
+        self
+    }
+
+    pub fn version(mut self, version: Option<u64>) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub fn signed(mut self, signed: bool) -> Self {
+        self.signed = signed;
+        self
+    }
+
+    /// Authenticate with a token instead of a URL signature
+    pub fn token(mut self, token: AuthToken) -> Self {
+        self.token = Some(token);
+        self
+    }
+
+    pub fn build(&self) -> Result<String> {
+        let transformation = self.transformation.map(ToString::to_string);
+
+        let mut path = vec![self.resource_type.to_string(), self.delivery_type.as_str().to_string()];
+        if self.signed && self.token.is_none() {
+            if self.config.api_secret.is_empty() {
+                anyhow::bail!("Signed URLs require the API secret");
+            }
+            let to_sign = [transformation.as_deref(), Some(self.public_id)]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join("/");
+            path.push(url_signature(&to_sign, &self.config.api_secret, self.config.signature_algorithm));
+        }
+        path.extend(transformation);
+        if let Some(version) = self.version {
+            path.push(format!("v{}", version));
+        }
+        path.push(smart_escape(self.public_id));
+
+        let url = format!("{}/{}", self.config.delivery_url(), path.join("/"));
+
+        match &self.token {
+            Some(token) => {
+                let key = self
+                    .config
+                    .auth_token_key
+                    .as_deref()
+                    .filter(|key| !key.is_empty())
+                    .context("Token authentication requires auth_token_key in the config")?;
+                let url_path = url::Url::parse(&url).context("Invalid delivery URL")?.path().to_string();
+                Ok(format!("{}?{}", url, token.generate(key, &url_path)?))
+            }
+            None => Ok(url),
+        }
+    }
+}
+
+impl AuthToken {
+    /// The `__cld_token__=exp=...~acl=...~hmac=...` query parameter.
+    ///
+    /// The HMAC-SHA256 is keyed with the hex-decoded token key and covers the
+    /// ACL, or the URL path when no ACL is given.
+    pub fn generate(&self, key: &str, url_path: &str) -> Result<String> {
+        let key = decode_hex(key).context("auth_token_key must be a hex string")?;
+
+        let mut parts = vec![format!("exp={}", self.expiration)];
+        if let Some(acl) = &self.acl {
+            parts.push(format!("acl={}", escape_to_lower(acl)));
+        }
+
+        let mut to_sign = parts.clone();
+        if self.acl.is_none() {
+            to_sign.push(format!("url={}", escape_to_lower(url_path)));
+        }
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&key).context("Invalid token key")?;
+        mac.update(to_sign.join("~").as_bytes());
+        let hmac = mac
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        parts.push(format!("hmac={}", hmac));
+
+        Ok(format!("{}={}", TOKEN_NAME, parts.join("~")))
+    }
+}
+
+/// The `s--XXXXXXXX--` path component: the first 8 characters of the URL-safe
+/// base64 digest of the signed path followed by the API secret
+fn url_signature(to_sign: &str, api_secret: &str, algorithm: SignatureAlgorithm) -> String {
+    let payload = format!("{}{}", to_sign, api_secret);
+    let digest = match algorithm {
+        SignatureAlgorithm::Sha1 => Sha1::digest(payload.as_bytes()).to_vec(),
+        SignatureAlgorithm::Sha256 => Sha256::digest(payload.as_bytes()).to_vec(),
+    };
+    format!("s--{}--", &URL_SAFE.encode(digest)[..8])
+}
+
+/// Percent-encode everything in a public ID except letters, digits and `_.-/:`
+fn smart_escape(public_id: &str) -> String {
+    let mut escaped = String::with_capacity(public_id.len());
+    for c in public_id.chars() {
+        if c.is_ascii_alphanumeric() || "_.-/:".contains(c) {
+            escaped.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    escaped
+}
+
+/// Percent-encode the characters that are reserved in tokens, with lowercase hex digits
+fn escape_to_lower(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if " \"#%&'/:;<=>?@[\\]^`{|}~".contains(c) {
+            escaped.push_str(&format!("%{:02x}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if value.len() % 2 != 0 {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(value.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN_KEY: &str = "00112233FF99";
+    const EXPIRATION: u64 = 1792308182;
+
+    fn config() -> CloudinaryConfig {
+        CloudinaryConfig {
+            cloud_name: "test123".to_string(),
+            api_key: "a".to_string(),
+            api_secret: "b".to_string(),
+            auth_token_key: Some(TOKEN_KEY.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn builds_plain_urls() {
+        let config = config();
+        let transformation: Transformation = "w_100,c_fill".parse().unwrap();
+        let url = DeliveryUrl::new(&config, "folder/my photo.jpg")
+            .transformation(Some(&transformation))
+            .version(Some(1234))
+            .build()
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://res.cloudinary.com/test123/image/upload/w_100,c_fill/v1234/folder/my%20photo.jpg"
+        );
+    }
+
+    // Expected values below are the ones produced by Cloudinary's own SDKs
+
+    #[test]
+    fn signs_urls() {
+        let config = config();
+        let url = DeliveryUrl::new(&config, "image.jpg")
+            .version(Some(1234))
+            .signed(true)
+            .build()
+            .unwrap();
+        assert_eq!(url, "https://res.cloudinary.com/test123/image/upload/s----SjmNDA--/v1234/image.jpg");
+    }
+
+    #[test]
+    fn signs_urls_with_transformations() {
+        let config = config();
+        let transformation: Transformation = "c_crop,h_20,w_10".parse().unwrap();
+        let url = DeliveryUrl::new(&config, "image.jpg")
+            .transformation(Some(&transformation))
+            .version(Some(1234))
+            .signed(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://res.cloudinary.com/test123/image/upload/s--Ai4Znfl3--/c_crop,h_20,w_10/v1234/image.jpg"
+        );
+    }
+
+    #[test]
+    fn signs_private_and_authenticated_urls_by_default() {
+        let config = config();
+        let url = DeliveryUrl::new(&config, "image.jpg")
+            .delivery_type(DeliveryType::Authenticated)
+            .build()
+            .unwrap();
+        assert!(url.starts_with("https://res.cloudinary.com/test123/image/authenticated/s--"));
+
+        let unsigned = CloudinaryConfig {
+            api_secret: String::new(),
+            ..config
+        };
+        assert!(DeliveryUrl::new(&unsigned, "image.jpg")
+            .delivery_type(DeliveryType::Private)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn generates_acl_tokens() {
+        let token = AuthToken {
+            expiration: EXPIRATION,
+            acl: Some("/image/*".to_string()),
+        };
+        assert_eq!(
+            token.generate(TOKEN_KEY, "/ignored").unwrap(),
+            "__cld_token__=exp=1792308182~acl=%2fimage%2f*~hmac=4b6677af65d37021ac6295bcecdf47cfd7c8311bcf4cc912583c6d4028fbb7ba"
+        );
+    }
+
+    #[test]
+    fn generates_url_tokens() {
+        let token = AuthToken {
+            expiration: EXPIRATION,
+            acl: None,
+        };
+        assert_eq!(
+            token.generate(TOKEN_KEY, "/test123/image/authenticated/sample.jpg").unwrap(),
+            "__cld_token__=exp=1792308182~hmac=78fc9100f4e4ed99cc038d22c90afbe006b7cfa0a7636ade512f47abe232c08c"
+        );
+    }
+
+    #[test]
+    fn appends_tokens_instead_of_signing() {
+        let config = config();
+        let url = DeliveryUrl::new(&config, "sample.jpg")
+            .delivery_type(DeliveryType::Authenticated)
+            .token(AuthToken {
+                expiration: EXPIRATION,
+                acl: None,
+            })
+            .build()
+            .unwrap();
+        assert_eq!(
+            url,
+            "https://res.cloudinary.com/test123/image/authenticated/sample.jpg?__cld_token__=exp=1792308182~hmac=78fc9100f4e4ed99cc038d22c90afbe006b7cfa0a7636ade512f47abe232c08c"
+        );
+    }
+
+    #[test]
+    fn rejects_non_hex_token_keys() {
+        let token = AuthToken {
+            expiration: EXPIRATION,
+            acl: None,
+        };
+        assert!(token.generate("xyz", "/").is_err());
+        assert!(token.generate("abc", "/").is_err());
+    }
+}
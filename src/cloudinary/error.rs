@@ -0,0 +1,183 @@
+use reqwest::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// Exit code for failures that are not Cloudinary API errors (configuration, I/O, network)
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_AUTH: i32 = 3;
+pub const EXIT_INVALID_PARAMS: i32 = 4;
+pub const EXIT_FILE_TOO_LARGE: i32 = 5;
+pub const EXIT_NOT_FOUND: i32 = 6;
+pub const EXIT_RATE_LIMITED: i32 = 7;
+pub const EXIT_SERVER: i32 = 8;
+
+/// An error response returned by the Cloudinary API
+#[derive(Error, Debug)]
+pub enum CloudinaryError {
+    #[error("Authentication failed: {0}")]
+    Auth(String),
+
+    #[error("Rate limit exceeded: {0}")]
+    RateLimited(String),
+
+    #[error("Invalid request: {0}")]
+    InvalidParams(String),
+
+    #[error("File too large: {0}")]
+    FileTooLarge(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Server error (HTTP {status}): {message}")]
+    Server { status: u16, message: String },
+}
+
+/// Body of an API error: `{"error": {"message": "..."}}`
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    error: ErrorMessage,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorMessage {
+    message: String,
+}
+
+impl CloudinaryError {
+    /// Classify a failed response by its status and error message
+    pub fn from_response(status: StatusCode, body: &str) -> Self {
+        let message = match serde_json::from_str::<ErrorBody>(body) {
+            Ok(body) => body.error.message,
+            Err(_) if body.trim().is_empty() => status.to_string(),
+            Err(_) => body.trim().to_string(),
+        };
+
+        match status.as_u16() {
+            401 | 403 => CloudinaryError::Auth(message),
+            420 | 429 => CloudinaryError::RateLimited(message),
+            404 => CloudinaryError::NotFound(message),
+            413 => CloudinaryError::FileTooLarge(message),
+            // Cloudinary reports size limits as a plain 400
+            400 if message.to_lowercase().contains("too large") => {
+                CloudinaryError::FileTooLarge(message)
+            }
+            code if status.is_server_error() => CloudinaryError::Server { status: code, message },
+            _ => CloudinaryError::InvalidParams(message),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CloudinaryError::Auth(_) => EXIT_AUTH,
+            CloudinaryError::RateLimited(_) => EXIT_RATE_LIMITED,
+            CloudinaryError::InvalidParams(_) => EXIT_INVALID_PARAMS,
+            CloudinaryError::FileTooLarge(_) => EXIT_FILE_TOO_LARGE,
+            CloudinaryError::NotFound(_) => EXIT_NOT_FOUND,
+            CloudinaryError::Server { .. } => EXIT_SERVER,
+        }
+    }
+}
+
+/// Process exit code for an error, based on the Cloudinary error it was caused by
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<CloudinaryError>())
+        .map(CloudinaryError::exit_code)
+        .unwrap_or(EXIT_FAILURE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    fn classify(code: u16, body: &str) -> CloudinaryError {
+        CloudinaryError::from_response(StatusCode::from_u16(code).unwrap(), body)
+    }
+
+    fn api_error(message: &str) -> String {
+        serde_json::json!({ "error": { "message": message } }).to_string()
+    }
+
+    #[test]
+    fn classifies_auth_failures() {
+        for code in [401, 403] {
+            match classify(code, &api_error("Invalid Signature")) {
+                CloudinaryError::Auth(message) => assert_eq!(message, "Invalid Signature"),
+                other => panic!("HTTP {} classified as {:?}", code, other),
+            }
+        }
+    }
+
+    #[test]
+    fn classifies_rate_limits() {
+        for code in [420, 429] {
+            assert!(matches!(classify(code, ""), CloudinaryError::RateLimited(_)), "{}", code);
+        }
+    }
+
+    #[test]
+    fn classifies_missing_resources() {
+        assert!(matches!(
+            classify(404, &api_error("Resource not found")),
+            CloudinaryError::NotFound(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_size_limits() {
+        assert!(matches!(classify(413, ""), CloudinaryError::FileTooLarge(_)));
+        assert!(matches!(
+            classify(400, &api_error("File size too large. Got 20000000. Maximum is 10485760.")),
+            CloudinaryError::FileTooLarge(_)
+        ));
+        assert!(matches!(
+            classify(400, &api_error("Invalid transformation")),
+            CloudinaryError::InvalidParams(_)
+        ));
+    }
+
+    #[test]
+    fn classifies_server_errors() {
+        for code in [500, 502, 503] {
+            match classify(code, "") {
+                CloudinaryError::Server { status, .. } => assert_eq!(status, code),
+                other => panic!("HTTP {} classified as {:?}", code, other),
+            }
+        }
+    }
+
+    #[test]
+    fn falls_back_to_plain_text_or_status() {
+        match classify(400, "  Bad things happened\n") {
+            CloudinaryError::InvalidParams(message) => assert_eq!(message, "Bad things happened"),
+            other => panic!("classified as {:?}", other),
+        }
+        match classify(403, "") {
+            CloudinaryError::Auth(message) => assert_eq!(message, "403 Forbidden"),
+            other => panic!("classified as {:?}", other),
+        }
+    }
+
+    #[test]
+    fn exit_code_follows_the_error_chain() {
+        let err = Err::<(), _>(classify(404, ""))
+            .context("Request failed after 1 attempt(s)")
+            .context("Failed to fetch sample")
+            .unwrap_err();
+        assert_eq!(exit_code(&err), EXIT_NOT_FOUND);
+
+        assert_eq!(exit_code(&anyhow::Error::new(classify(401, ""))), EXIT_AUTH);
+        assert_eq!(exit_code(&anyhow::Error::new(classify(429, ""))), EXIT_RATE_LIMITED);
+        assert_eq!(exit_code(&anyhow::Error::new(classify(413, ""))), EXIT_FILE_TOO_LARGE);
+        assert_eq!(exit_code(&anyhow::Error::new(classify(400, ""))), EXIT_INVALID_PARAMS);
+        assert_eq!(exit_code(&anyhow::Error::new(classify(500, ""))), EXIT_SERVER);
+    }
+
+    #[test]
+    fn other_errors_exit_with_failure() {
+        let err = anyhow::anyhow!("Config file not found").context("Failed to load configuration");
+        assert_eq!(exit_code(&err), EXIT_FAILURE);
+    }
+}
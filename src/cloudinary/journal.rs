@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use super::UploadOptions;
+use crate::config::Config;
+
+/// On-disk record of a chunked upload that has not finished yet.
+///
+/// A journal is written before the first chunk is sent and updated after every
+/// chunk Cloudinary acknowledges, so an interrupted upload can continue with the
+/// same upload id from the last acknowledged byte.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UploadJournal {
+    pub upload_id: String,
+    pub file_path: PathBuf,
+    pub file_size: u64,
+    pub mtime: u64,
+    pub chunk_size: u64,
+    /// Acknowledged byte ranges, inclusive on both ends
+    #[serde(default)]
+    pub completed: Vec<(u64, u64)>,
+    #[serde(default)]
+    pub options: UploadOptions,
+}
+
+impl UploadJournal {
+    pub fn new(
+        upload_id: String,
+        file_path: &Path,
+        file_size: u64,
+        mtime: u64,
+        chunk_size: u64,
+        options: UploadOptions,
+    ) -> Self {
+        UploadJournal {
+            upload_id,
+            file_path: file_path.to_path_buf(),
+            file_size,
+            mtime,
+            chunk_size,
+            completed: Vec::new(),
+            options,
+        }
+    }
+
+    /// Directory holding all upload journals
+    pub fn dir() -> PathBuf {
+        Config::config_dir().join("uploads")
+    }
+
+    fn journal_path(file_path: &Path) -> PathBuf {
+        let mut hasher = Sha1::new();
+        hasher.update(file_path.to_string_lossy().as_bytes());
+        Self::dir().join(format!("{:x}.json", hasher.finalize()))
+    }
+
+    /// Load the journal for `file_path`, if one exists
+    pub fn load(file_path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(Self::journal_path(file_path)).ok()?;
+        serde_json::from_str(&contents).ok()
+    }
+
+    /// List every interrupted upload recorded on disk
+    pub fn list() -> Result<Vec<Self>> {
+        let dir = Self::dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut journals = Vec::new();
+        for entry in fs::read_dir(&dir).context("Failed to read upload journal directory")? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read upload journal {}", path.display()))?;
+            if let Ok(journal) = serde_json::from_str::<UploadJournal>(&contents) {
+                journals.push(journal);
+            }
+        }
+
+        journals.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        Ok(journals)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let dir = Self::dir();
+        fs::create_dir_all(&dir).context("Failed to create upload journal directory")?;
+
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(Self::journal_path(&self.file_path), contents)
+            .context("Failed to write upload journal")?;
+
+        Ok(())
+    }
+
+    pub fn remove(&self) -> Result<()> {
+        let path = Self::journal_path(&self.file_path);
+        if path.exists() {
+            fs::remove_file(path).context("Failed to remove upload journal")?;
+        }
+        Ok(())
+    }
+
+    /// Whether the journal still describes the file as it is on disk now
+    pub fn matches(&self, file_size: u64, mtime: u64, chunk_size: u64) -> bool {
+        self.file_size == file_size && self.mtime == mtime && self.chunk_size == chunk_size
+    }
+
+    /// Record a byte range Cloudinary has acknowledged
+    pub fn record(&mut self, start: u64, end: u64) {
+        self.completed.push((start, end));
+        self.completed.sort_unstable();
+    }
+
+    /// First byte not covered by the contiguous acknowledged ranges from offset 0
+    pub fn next_offset(&self) -> u64 {
+        let mut offset = 0;
+        for &(start, end) in &self.completed {
+            if start > offset {
+                break;
+            }
+            offset = offset.max(end + 1);
+        }
+        offset
+    }
+}
+
+/// Modification time of a file in seconds since the Unix epoch
+pub fn file_mtime(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn journal() -> UploadJournal {
+        UploadJournal::new(
+            "abc123".to_string(),
+            Path::new("/tmp/video.mp4"),
+            100,
+            1_700_000_000,
+            10,
+            UploadOptions::default(),
+        )
+    }
+
+    #[test]
+    fn empty_journal_starts_at_zero() {
+        assert_eq!(journal().next_offset(), 0);
+    }
+
+    #[test]
+    fn continues_after_contiguous_ranges() {
+        let mut journal = journal();
+        journal.record(0, 9);
+        assert_eq!(journal.next_offset(), 10);
+        journal.record(10, 19);
+        journal.record(20, 29);
+        assert_eq!(journal.next_offset(), 30);
+    }
+
+    #[test]
+    fn sorts_ranges_recorded_out_of_order() {
+        let mut journal = journal();
+        journal.record(20, 29);
+        journal.record(0, 9);
+        journal.record(10, 19);
+        assert_eq!(journal.completed, vec![(0, 9), (10, 19), (20, 29)]);
+        assert_eq!(journal.next_offset(), 30);
+    }
+
+    #[test]
+    fn stops_at_the_first_gap() {
+        let mut journal = journal();
+        journal.record(0, 9);
+        journal.record(20, 29);
+        journal.record(30, 39);
+        assert_eq!(journal.next_offset(), 10);
+    }
+
+    #[test]
+    fn starts_over_without_the_first_range() {
+        let mut journal = journal();
+        journal.record(10, 19);
+        assert_eq!(journal.next_offset(), 0);
+    }
+
+    #[test]
+    fn tolerates_duplicate_and_overlapping_ranges() {
+        let mut journal = journal();
+        journal.record(0, 9);
+        journal.record(0, 9);
+        journal.record(5, 14);
+        assert_eq!(journal.next_offset(), 15);
+    }
+
+    #[test]
+    fn matches_only_the_same_file_and_chunk_size() {
+        let journal = journal();
+        assert!(journal.matches(100, 1_700_000_000, 10));
+        assert!(!journal.matches(101, 1_700_000_000, 10));
+        assert!(!journal.matches(100, 1_700_000_001, 10));
+        assert!(!journal.matches(100, 1_700_000_000, 20));
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut journal = journal();
+        journal.record(0, 9);
+        let loaded: UploadJournal = serde_json::from_str(&serde_json::to_string(&journal).unwrap()).unwrap();
+        assert_eq!(loaded.upload_id, "abc123");
+        assert_eq!(loaded.completed, vec![(0, 9)]);
+        assert_eq!(loaded.next_offset(), 10);
+    }
+}
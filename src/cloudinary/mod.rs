@@ -1,13 +1,47 @@
 use anyhow::{Context, Result};
+use bytes::Bytes;
+use indicatif::ProgressBar;
 use reqwest::multipart::{Form, Part};
+use reqwest::Body;
 use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::future::Future;
+use std::io::SeekFrom;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
 use mime_guess::from_path;
 
-use crate::config::CloudinaryConfig;
+use crate::config::{CloudinaryConfig, DeliveryType, FolderMode, ResourceType, UploadConfig};
+use crate::utils::print_warning;
+
+pub mod admin;
+pub mod delivery;
+pub mod error;
+pub mod journal;
+pub mod public_id;
+pub mod retry;
+pub mod scheduler;
+pub mod signature;
+pub mod sniff;
+pub mod source;
+pub mod transformation;
+
+use delivery::DeliveryUrl;
+use journal::{file_mtime, UploadJournal};
+pub use error::CloudinaryError;
+pub use source::UploadSource;
+pub use transformation::Transformation;
+
+use signature::Params;
+use sniff::{mime_hint, sniff_file, sniff_resource_type};
+use source::{mime_resource_type, remote_resource_type};
+use retry::RetryPolicy;
+
+/// Smallest chunk size Cloudinary accepts for chunked uploads (except the last chunk)
+const MIN_CHUNK_SIZE: u64 = 5 * 1024 * 1024;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UploadResponse {
@@ -23,124 +57,280 @@ pub struct UploadResponse {
     pub bytes: u64,
     pub url: String,
     pub secure_url: String,
+    #[serde(default)]
+    pub context: Option<serde_json::Value>,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    #[serde(default)]
+    pub eager: Option<Vec<EagerResponse>>,
+    #[serde(rename = "type", default)]
+    pub delivery_type: DeliveryType,
+}
+
+/// A derivative generated at upload time by an eager transformation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EagerResponse {
+    /// Missing from placeholders of pending asynchronous derivatives
+    #[serde(default)]
+    pub transformation: String,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub bytes: Option<u64>,
+    pub format: Option<String>,
+    pub url: Option<String>,
+    pub secure_url: Option<String>,
+    /// Set to `processing` while an asynchronous eager transformation is pending
+    pub status: Option<String>,
+}
+
+/// An API response carrying an asset's contextual and structured metadata
+pub trait AssetMetadata {
+    fn context(&self) -> Option<&serde_json::Value>;
+    fn metadata(&self) -> Option<&serde_json::Value>;
+
+    /// Contextual metadata as `key=value` pairs, as Cloudinary nests it under `custom`
+    fn context_pairs(&self) -> Vec<String> {
+        key_value_pairs(self.context().and_then(|c| c.get("custom")))
+    }
+
+    /// Structured metadata as `external_id=value` pairs
+    fn metadata_pairs(&self) -> Vec<String> {
+        key_value_pairs(self.metadata())
+    }
+}
+
+impl AssetMetadata for UploadResponse {
+    fn context(&self) -> Option<&serde_json::Value> {
+        self.context.as_ref()
+    }
+
+    fn metadata(&self) -> Option<&serde_json::Value> {
+        self.metadata.as_ref()
+    }
+}
+
+fn key_value_pairs(value: Option<&serde_json::Value>) -> Vec<String> {
+    let Some(object) = value.and_then(|v| v.as_object()) else {
+        return Vec::new();
+    };
+
+    object
+        .iter()
+        .map(|(key, value)| match value.as_str() {
+            Some(text) => format!("{}={}", key, text),
+            None => format!("{}={}", key, value),
+        })
+        .collect()
+}
+
+/// Per-upload settings chosen on the command line
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UploadOptions {
+    pub folder: Option<String>,
+    pub transformation: Option<Transformation>,
+    #[serde(default)]
+    pub upload_preset: Option<String>,
+    /// Upload without a signature, relying on an unsigned upload preset
+    #[serde(default)]
+    pub unsigned: bool,
+    #[serde(default)]
+    pub public_id: Option<String>,
+    #[serde(default)]
+    pub use_filename: Option<bool>,
+    #[serde(default)]
+    pub unique_filename: Option<bool>,
+    #[serde(default)]
+    pub overwrite: Option<bool>,
+    #[serde(default)]
+    pub folder_mode: FolderMode,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Contextual metadata as key/value pairs
+    #[serde(default)]
+    pub context: Vec<(String, String)>,
+    /// Structured metadata as external id/value pairs
+    #[serde(default)]
+    pub metadata: Vec<(String, String)>,
+    /// Derivatives to generate at upload time
+    #[serde(default)]
+    pub eager: Vec<Transformation>,
+    /// Generate eager derivatives in the background instead of during the upload
+    #[serde(default)]
+    pub eager_async: bool,
+    /// URL notified when asynchronous processing has finished
+    #[serde(default)]
+    pub notification_url: Option<String>,
+    /// Upload endpoint to use instead of detecting it from the file
+    #[serde(default)]
+    pub resource_type: Option<ResourceType>,
+    /// Access control for the stored asset
+    #[serde(default)]
+    pub delivery_type: Option<DeliveryType>,
 }
 
 #[derive(Clone)]
 pub struct CloudinaryUploader {
     config: CloudinaryConfig,
+    upload_config: UploadConfig,
+    retry_policy: RetryPolicy,
     client: reqwest::Client,
+    progress: Option<ProgressBar>,
 }
 
 impl CloudinaryUploader {
-    pub fn new(config: CloudinaryConfig) -> Self {
+    pub fn new(config: CloudinaryConfig, upload_config: UploadConfig) -> Self {
         CloudinaryUploader {
+            retry_policy: RetryPolicy::from_config(&upload_config.retry),
+            client: retry::http_client(&upload_config.retry),
             config,
-            client: reqwest::Client::new(),
+            upload_config,
+            progress: None,
+        }
+    }
+
+    /// Report retried attempts on the given progress bar
+    pub fn with_progress(mut self, progress: ProgressBar) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Upload a single source with the given options
+    pub async fn upload(&self, source: &UploadSource, options: &UploadOptions) -> Result<UploadResponse> {
+        let resource_type = self.source_resource_type(source, options);
+        match source {
+            UploadSource::File(path) => self.upload_file(path, resource_type, options).await,
+            UploadSource::Url(url) => self.upload_remote(url, resource_type, options).await,
+            UploadSource::Bytes {
+                data,
+                file_name,
+                mime_type,
+                ..
+            } => {
+                self.upload_bytes(data, file_name, mime_type, resource_type, options)
+                    .await
+            }
         }
     }
 
     pub async fn upload_file(
         &self,
         file_path: &Path,
-        folder: Option<&str>,
-        transformation: Option<&str>,
+        resource_type: &str,
+        options: &UploadOptions,
     ) -> Result<UploadResponse> {
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .context("Failed to get system time")?
-            .as_secs()
-            .to_string();
-
-        let mut file = File::open(file_path).await?;
-        let mut buffer = Vec::new();
-        file.read_to_end(&mut buffer).await?;
+        let metadata = tokio::fs::metadata(file_path)
+            .await
+            .context("Failed to read file metadata")?;
+        let file_size = metadata.len();
 
         let file_name = file_path
             .file_name()
             .and_then(|n| n.to_str())
             .context("Invalid file name")?;
 
-        let resource_type = self.determine_resource_type(file_path);
-        
-        // Build the form and collect parameters for signature
-        let mut form = Form::new()
-            .text("api_key", self.config.api_key.clone())
-            .text("timestamp", timestamp.clone());
-            
-        // Create a map of parameters for signature generation
-        use std::collections::HashMap;
-        let mut params = HashMap::new();
-        params.insert("timestamp".to_string(), timestamp.clone());
+        // Also validates the options before a chunked upload journal is written
+        let sorted_params = self.upload_params(options)?;
 
-        // Add folder if specified
-        if let Some(folder_name) = folder.or(Some(&self.config.default_folder)) {
-            if !folder_name.is_empty() {
-                form = form.text("folder", folder_name.to_string());
-                params.insert("folder".to_string(), folder_name.to_string());
-            }
-        }
+        let url = self.upload_endpoint(resource_type);
+        let mime_type = from_path(file_path).first_or_octet_stream();
 
-        // Add transformation if specified
-        if let Some(transform) = transformation {
-            match transform {
-                "webp" => {
-                    form = form.text("format", "webp");
-                    params.insert("format".to_string(), "webp".to_string());
-                },
-                "avif" => {
-                    form = form.text("format", "avif");
-                    params.insert("format".to_string(), "avif".to_string());
-                },
-                _ => {}
-            }
+        if file_size > self.upload_config.chunk_threshold {
+            let file_path = file_path
+                .canonicalize()
+                .context("Failed to resolve file path")?;
+            let chunk_size = self.chunk_size();
+            let mtime = file_mtime(&metadata);
+
+            // Continue an interrupted upload of the same, unchanged file
+            let mut journal = match UploadJournal::load(&file_path) {
+                Some(journal) if journal.matches(file_size, mtime, chunk_size) => journal,
+                _ => UploadJournal::new(
+                    unique_upload_id(&file_path),
+                    &file_path,
+                    file_size,
+                    mtime,
+                    chunk_size,
+                    options.clone(),
+                ),
+            };
+            journal.options = options.clone();
+            journal.save()?;
+
+            return self
+                .upload_chunked(&url, &mut journal, file_name, mime_type.as_ref(), options)
+                .await;
         }
 
-        // Generate signature - sort parameters alphabetically as required by Cloudinary
-        let mut sorted_params: Vec<(String, String)> = params.into_iter().collect();
-        sorted_params.sort_by(|a, b| a.0.cmp(&b.0));
-        
-        // Build the string to sign
-        let string_to_sign = sorted_params
-            .iter()
-            .map(|(key, value)| format!("{}={}", key, value))
-            .collect::<Vec<String>>()
-            .join("&");
-        
-        // Generate the signature using SHA-1
-        use sha1::{Digest, Sha1};
-        let mut hasher = Sha1::new();
-        hasher.update(string_to_sign.as_bytes());
-        hasher.update(self.config.api_secret.as_bytes());
-        let signature = format!("{:x}", hasher.finalize());
-
-        form = form.text("signature", signature);
-
-        // Add file part
-        let mime_type = from_path(file_path).first_or_octet_stream();
-        let part = Part::bytes(buffer)
-            .file_name(file_name.to_string())
-            .mime_str(mime_type.as_ref())?;
+        let url = &url;
+        let mime_type = mime_type.as_ref();
+        let sorted_params = &sorted_params;
 
-        form = form.part("file", part);
+        let response = self
+            .send_with_retry(file_name, || async move {
+                // Add file part, streamed from disk rather than buffered in memory
+                let file = File::open(file_path).await?;
+                let part = file_part(file, file_size, file_name, mime_type)?;
 
-        // Send request
-        let url = format!(
-            "https://api.cloudinary.com/v1_1/{}/{}/upload",
-            self.config.cloud_name, resource_type
-        );
+                let form = self.build_form(sorted_params).part("file", part);
+                Ok(self.client.post(url).multipart(form))
+            })
+            .await?;
+
+        let upload_response = response
+            .json::<UploadResponse>()
+            .await
+            .context("Failed to parse upload response")?;
+
+        Ok(upload_response)
+    }
+
+    /// Upload a file that Cloudinary fetches itself from an http(s) URL
+    pub async fn upload_remote(
+        &self,
+        url: &str,
+        resource_type: &str,
+        options: &UploadOptions,
+    ) -> Result<UploadResponse> {
+        let sorted_params = &self.upload_params(options)?;
+        let endpoint = &self.upload_endpoint(resource_type);
 
         let response = self
-            .client
-            .post(&url)
-            .multipart(form)
-            .send()
+            .send_with_retry(url, || async move {
+                let form = self.build_form(sorted_params).text("file", url.to_string());
+                Ok(self.client.post(endpoint).multipart(form))
+            })
+            .await?;
+
+        let upload_response = response
+            .json::<UploadResponse>()
             .await
-            .context("Failed to send upload request")?;
+            .context("Failed to parse upload response")?;
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            anyhow::bail!("Upload failed: {}", error_text);
-        }
+        Ok(upload_response)
+    }
+
+    /// Upload data held in memory as a file part named `file_name`
+    pub async fn upload_bytes(
+        &self,
+        data: &Bytes,
+        file_name: &str,
+        mime_type: &str,
+        resource_type: &str,
+        options: &UploadOptions,
+    ) -> Result<UploadResponse> {
+        let sorted_params = &self.upload_params(options)?;
+        let endpoint = &self.upload_endpoint(resource_type);
+
+        let response = self
+            .send_with_retry(file_name, || async move {
+                let part = Part::stream_with_length(Body::from(data.clone()), data.len() as u64)
+                    .file_name(file_name.to_string())
+                    .mime_str(mime_type)?;
+
+                let form = self.build_form(sorted_params).part("file", part);
+                Ok(self.client.post(endpoint).multipart(form))
+            })
+            .await?;
 
         let upload_response = response
             .json::<UploadResponse>()
@@ -150,28 +340,627 @@ impl CloudinaryUploader {
         Ok(upload_response)
     }
 
-    fn determine_resource_type(&self, file_path: &Path) -> &'static str {
-        let mime = from_path(file_path).first_or_octet_stream();
-        let mime_type = mime.type_().as_str();
+    /// Collect the form fields for `options`, sorted and, unless the upload is
+    /// unsigned, signed with the API secret
+    fn upload_params(&self, options: &UploadOptions) -> Result<Vec<(String, String)>> {
+        let upload_preset = options
+            .upload_preset
+            .as_deref()
+            .or(self.config.upload_preset.as_deref())
+            .filter(|preset| !preset.is_empty());
+
+        // Without a secret there is nothing to sign with, so fall back to an unsigned upload
+        let unsigned = options.unsigned || self.config.api_secret.is_empty();
 
-        match mime_type {
-            "image" => "image",
-            "video" => "video",
-            "audio" => "raw",
-            _ => "auto",
+        let mut params = Params::new();
+
+        if let Some(preset) = upload_preset {
+            params.insert("upload_preset".to_string(), preset.into());
+        } else if unsigned {
+            anyhow::bail!(
+                "Unsigned uploads require an upload preset (use --preset or set upload_preset in the config)"
+            );
+        }
+
+        // Add folder if specified
+        if let Some(folder_name) = options.folder.as_deref().or(Some(&self.config.default_folder)) {
+            if !folder_name.is_empty() {
+                let folder_param = match options.folder_mode {
+                    FolderMode::Fixed => "folder",
+                    FolderMode::Dynamic => "asset_folder",
+                };
+                params.insert(folder_param.to_string(), folder_name.into());
+            }
+        }
+
+        if let Some(public_id) = options.public_id.as_deref().filter(|id| !id.is_empty()) {
+            params.insert("public_id".to_string(), public_id.into());
+        }
+
+        if !options.tags.is_empty() {
+            if let Some(tag) = options.tags.iter().find(|tag| tag.contains(',')) {
+                anyhow::bail!("Tag '{}' contains ',', which would split it into several tags", tag);
+            }
+            params.insert("tags".to_string(), options.tags.join(",").into());
         }
+
+        if !options.context.is_empty() {
+            params.insert("context".to_string(), encode_key_values(&options.context).into());
+        }
+
+        if !options.metadata.is_empty() {
+            params.insert("metadata".to_string(), encode_key_values(&options.metadata).into());
+        }
+
+        if !options.eager.is_empty() {
+            let eager = options
+                .eager
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("|");
+            params.insert("eager".to_string(), eager.into());
+        }
+
+        if options.eager_async {
+            params.insert("eager_async".to_string(), "true".into());
+        }
+
+        if let Some(notification_url) = options.notification_url.as_deref() {
+            params.insert("notification_url".to_string(), notification_url.into());
+        }
+
+        if let Some(delivery_type) = options.delivery_type {
+            params.insert("type".to_string(), delivery_type.as_str().into());
+        }
+
+        let flags = [
+            ("use_filename", options.use_filename),
+            ("unique_filename", options.unique_filename),
+            ("overwrite", options.overwrite),
+        ];
+        for (name, value) in flags {
+            if let Some(value) = value {
+                params.insert(name.to_string(), value.to_string().into());
+            }
+        }
+
+        // Add transformation if specified. A plain format conversion converts the
+        // stored asset; anything else is applied as an incoming transformation
+        if let Some(transformation) = &options.transformation {
+            transformation.check_incoming()?;
+            match transformation.format_only() {
+                Some(format) => {
+                    params.insert("format".to_string(), format.into());
+                }
+                None => {
+                    params.insert("transformation".to_string(), transformation.to_string().into());
+                }
+            }
+        }
+
+        if unsigned {
+            return Ok(signature::form_fields(params));
+        }
+
+        self.sign_params(params)
     }
 
-    pub fn get_url(&self, public_id: &str, resource_type: &str, transformation: Option<&str>) -> String {
-        let base_url = format!(
-            "https://res.cloudinary.com/{}/{}/upload",
-            self.config.cloud_name, resource_type
-        );
+    /// Add the timestamp, signature and API key to a signed request
+    fn sign_params(&self, params: Params) -> Result<Vec<(String, String)>> {
+        if self.config.api_key.is_empty() {
+            anyhow::bail!("Signed uploads require an API key; use --unsigned with an upload preset instead");
+        }
+
+        signature::signed_fields(params, &self.config)
+    }
+
+    /// Upload a large file as a sequence of chunks sharing one upload id.
+    ///
+    /// Every chunk request is signed afresh, so a long upload never sends a
+    /// timestamp Cloudinary would reject as expired. Cloudinary answers the
+    /// intermediate chunks with a partial response and returns the full upload
+    /// result once the final byte range has been received. Upload starts after
+    /// the last byte range acknowledged in `journal`, which is updated as chunks
+    /// complete and removed once the upload has finished.
+    async fn upload_chunked(
+        &self,
+        url: &str,
+        journal: &mut UploadJournal,
+        file_name: &str,
+        mime_type: &str,
+        options: &UploadOptions,
+    ) -> Result<UploadResponse> {
+        let file_path = journal.file_path.clone();
+        let file_size = journal.file_size;
+        let chunk_size = journal.chunk_size;
+        // The final chunk is never recorded, so this is always before its start
+        let mut start = journal.next_offset();
+
+        let file_path = &file_path;
+        let upload_id = &journal.upload_id.clone();
+
+        loop {
+            let end = (start + chunk_size).min(file_size) - 1;
+            let label = format!("{} (bytes {}-{})", file_name, start, end);
+
+            let response = self
+                .send_with_retry(&label, || async move {
+                    // Each chunk streams its byte range from its own file handle, so at
+                    // most one read buffer per chunk request is held in memory
+                    let mut file = File::open(file_path).await?;
+                    file.seek(SeekFrom::Start(start))
+                        .await
+                        .context("Failed to seek to file chunk")?;
+                    let part = file_part(file.take(end - start + 1), end - start + 1, file_name, mime_type)?;
+
+                    let params = self.upload_params(options)?;
+                    let form = self.build_form(&params).part("file", part);
+                    Ok(self
+                        .client
+                        .post(url)
+                        .header("X-Unique-Upload-Id", upload_id)
+                        .header("Content-Range", format!("bytes {}-{}/{}", start, end, file_size))
+                        .multipart(form))
+                })
+                .await?;
+
+            if end + 1 >= file_size {
+                let upload_response = response
+                    .json::<UploadResponse>()
+                    .await
+                    .context("Failed to parse upload response")?;
+                journal.remove()?;
+                return Ok(upload_response);
+            }
+
+            journal.record(start, end);
+            journal.save()?;
+            start = end + 1;
+        }
+    }
+
+    /// Send the request produced by `build`, retrying transient failures
+    async fn send_with_retry<F, Fut>(&self, label: &str, build: F) -> Result<reqwest::Response>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<reqwest::RequestBuilder>>,
+    {
+        self.retry_policy.send(self.progress.as_ref(), label, build).await
+    }
+
+    fn build_form(&self, params: &[(String, String)]) -> Form {
+        params
+            .iter()
+            .fold(Form::new(), |form, (key, value)| form.text(key.clone(), value.clone()))
+    }
+
+    fn chunk_size(&self) -> u64 {
+        if self.upload_config.chunk_size < MIN_CHUNK_SIZE {
+            print_warning(&format!(
+                "Chunk size {} is below Cloudinary's 5 MB minimum, using {} instead",
+                self.upload_config.chunk_size, MIN_CHUNK_SIZE
+            ));
+            return MIN_CHUNK_SIZE;
+        }
+        self.upload_config.chunk_size
+    }
+
+    fn upload_endpoint(&self, resource_type: &str) -> String {
+        format!(
+            "{}/{}/{}/upload",
+            self.config.api_url(),
+            self.config.cloud_name,
+            resource_type
+        )
+    }
+
+    /// Resource type of a local file from its content, falling back to its extension
+    pub fn determine_resource_type(&self, file_path: &Path) -> &'static str {
+        sniff_file(file_path).unwrap_or_else(|| {
+            let mime = from_path(file_path).first_or_octet_stream();
+            mime_resource_type(mime.essence_str())
+        })
+    }
+
+    /// Resource type used for the upload endpoint of `source`.
+    ///
+    /// An explicit resource type in the options wins, then an override for the
+    /// file extension from the config, then a MIME type given with `--mime`,
+    /// then detection from the content and finally the extension itself.
+    pub fn source_resource_type(&self, source: &UploadSource, options: &UploadOptions) -> &'static str {
+        if let Some(resource_type) = options.resource_type {
+            return resource_type.as_str();
+        }
+        if let Some(resource_type) = self.extension_resource_type(source) {
+            return resource_type.as_str();
+        }
+
+        match source {
+            UploadSource::File(path) => self.determine_resource_type(path),
+            UploadSource::Url(url) => remote_resource_type(url),
+            UploadSource::Bytes {
+                mime_type,
+                mime_explicit: true,
+                ..
+            } => mime_resource_type(mime_type),
+            UploadSource::Bytes { data, mime_type, .. } => {
+                sniff_resource_type(data, mime_hint(mime_type))
+                    .unwrap_or_else(|| mime_resource_type(mime_type))
+            }
+        }
+    }
+
+    /// Resource type configured for the extension of `source`, if any
+    fn extension_resource_type(&self, source: &UploadSource) -> Option<ResourceType> {
+        let overrides = &self.upload_config.resource_types;
+        if overrides.is_empty() {
+            return None;
+        }
+
+        let name = match source {
+            UploadSource::File(path) => path.to_string_lossy().into_owned(),
+            UploadSource::Url(url) => url::Url::parse(url).map(|u| u.path().to_string()).unwrap_or_default(),
+            UploadSource::Bytes { file_name, .. } => file_name.clone(),
+        };
+        let extension = Path::new(&name).extension()?.to_string_lossy().to_lowercase();
+
+        overrides
+            .iter()
+            .find(|(key, _)| key.trim_start_matches('.').eq_ignore_ascii_case(&extension))
+            .map(|(_, resource_type)| *resource_type)
+    }
+
+    /// Delivery URL of an asset, signed when the delivery type requires it
+    pub fn get_url(
+        &self,
+        public_id: &str,
+        resource_type: &str,
+        delivery_type: DeliveryType,
+        transformation: Option<&Transformation>,
+    ) -> Result<String> {
+        DeliveryUrl::new(&self.config, public_id)
+            .resource_type(resource_type)
+            .delivery_type(delivery_type)
+            .transformation(transformation)
+            .build()
+    }
+}
+
+/// Join pairs into Cloudinary's `key=value|key=value` format, escaping `=` and
+/// `|` in values with a backslash
+fn encode_key_values(pairs: &[(String, String)]) -> String {
+    pairs
+        .iter()
+        .map(|(key, value)| {
+            let escaped = value.replace('=', "\\=").replace('|', "\\|");
+            format!("{}={}", key, escaped)
+        })
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Build a multipart file part that streams `len` bytes from `reader`
+fn file_part<R>(reader: R, len: u64, file_name: &str, mime_type: &str) -> Result<Part>
+where
+    R: AsyncRead + Send + Sync + 'static,
+{
+    let body = Body::wrap_stream(ReaderStream::new(reader));
+    let part = Part::stream_with_length(body, len)
+        .file_name(file_name.to_string())
+        .mime_str(mime_type)?;
+    Ok(part)
+}
+
+/// Generate an id shared by all chunks of a single upload
+fn unique_upload_id(file_path: &Path) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    let mut hasher = Sha1::new();
+    hasher.update(file_path.to_string_lossy().as_bytes());
+    hasher.update(nanos.to_le_bytes());
+    hasher.update(std::process::id().to_le_bytes());
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+
+    /// A request received by the local API server
+    pub(crate) struct ReceivedRequest {
+        pub method: String,
+        /// Path and query string
+        pub target: String,
+        pub headers: HashMap<String, String>,
+        pub body: Vec<u8>,
+    }
+
+    impl ReceivedRequest {
+        pub fn path(&self) -> &str {
+            self.target.split('?').next().unwrap_or_default()
+        }
+
+        /// Decoded query parameters, in order
+        pub fn query(&self) -> Vec<(String, String)> {
+            let query = self.target.split_once('?').map(|(_, query)| query).unwrap_or_default();
+            url::form_urlencoded::parse(query.as_bytes()).into_owned().collect()
+        }
 
-        match transformation {
-            Some("webp") => format!("{}/f_webp/{}", base_url, public_id),
-            Some("avif") => format!("{}/f_avif/{}", base_url, public_id),
-            _ => format!("{}/{}", base_url, public_id),
+        /// Decoded fields of a urlencoded form body, in order
+        pub fn form(&self) -> Vec<(String, String)> {
+            url::form_urlencoded::parse(&self.body).into_owned().collect()
         }
+
+        /// Value of a text field in the multipart body
+        fn field(&self, name: &str) -> Option<String> {
+            let body = String::from_utf8_lossy(&self.body);
+            let marker = format!("name=\"{}\"\r\n\r\n", name);
+            let start = body.find(&marker)? + marker.len();
+            let end = start + body[start..].find("\r\n")?;
+            Some(body[start..end].to_string())
+        }
+    }
+
+    /// Read one HTTP/1.1 request with a Content-Length body
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> ReceivedRequest {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 64 * 1024];
+        let header_end = loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "connection closed before the request headers ended");
+            data.extend_from_slice(&buf[..n]);
+            if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+
+        let head = String::from_utf8_lossy(&data[..header_end]).into_owned();
+        let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+        let method = request_line.next().unwrap_or_default().to_string();
+        let target = request_line.next().unwrap_or_default().to_string();
+
+        let headers: HashMap<String, String> = head
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+        let content_length: usize = headers
+            .get("content-length")
+            .map_or(0, |length| length.parse().unwrap());
+
+        let mut body = data.split_off(header_end);
+        while body.len() < content_length {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "connection closed before the request body ended");
+            body.extend_from_slice(&buf[..n]);
+        }
+
+        ReceivedRequest {
+            method,
+            target,
+            headers,
+            body,
+        }
+    }
+
+    /// Serve `responses` in order, one per connection, reporting each request received
+    pub(crate) async fn serve(responses: Vec<String>) -> (String, mpsc::UnboundedReceiver<ReceivedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let request = read_request(&mut stream).await;
+                tx.send(request).unwrap();
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                );
+                stream.write_all(reply.as_bytes()).await.unwrap();
+                stream.shutdown().await.unwrap();
+            }
+        });
+
+        (base_url, rx)
+    }
+
+    #[tokio::test]
+    async fn uploads_large_files_in_chunks() {
+        let dir = std::env::temp_dir().join(format!("cld-upload-chunked-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("large.bin");
+        let file_size = 2 * MIN_CHUNK_SIZE + 1234;
+        let contents: Vec<u8> = (0..file_size).map(|i| (i % 251) as u8).collect();
+        std::fs::write(&file_path, &contents).unwrap();
+
+        let final_response = serde_json::json!({
+            "public_id": "large",
+            "version": 1,
+            "signature": "abc",
+            "format": "bin",
+            "resource_type": "raw",
+            "created_at": "2024-01-01T00:00:00Z",
+            "bytes": file_size,
+            "url": "http://res.cloudinary.com/demo/raw/upload/v1/large",
+            "secure_url": "https://res.cloudinary.com/demo/raw/upload/v1/large",
+        });
+        let (base_url, mut requests) = serve(vec![
+            r#"{"done":false}"#.to_string(),
+            r#"{"done":false}"#.to_string(),
+            final_response.to_string(),
+        ])
+        .await;
+
+        let config = CloudinaryConfig {
+            cloud_name: "demo".to_string(),
+            api_key: "key".to_string(),
+            api_secret: "secret".to_string(),
+            api_base_url: Some(base_url),
+            ..Default::default()
+        };
+        let upload_config = UploadConfig {
+            chunk_threshold: MIN_CHUNK_SIZE,
+            chunk_size: MIN_CHUNK_SIZE,
+            ..Default::default()
+        };
+        let uploader = CloudinaryUploader::new(config, upload_config);
+
+        let response = uploader
+            .upload_file(&file_path, "raw", &UploadOptions::default())
+            .await
+            .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(response.public_id, "large");
+        assert_eq!(response.bytes, file_size);
+
+        let expected_ranges = [
+            format!("bytes 0-{}/{}", MIN_CHUNK_SIZE - 1, file_size),
+            format!("bytes {}-{}/{}", MIN_CHUNK_SIZE, 2 * MIN_CHUNK_SIZE - 1, file_size),
+            format!("bytes {}-{}/{}", 2 * MIN_CHUNK_SIZE, file_size - 1, file_size),
+        ];
+        let mut upload_id = None;
+        for expected_range in &expected_ranges {
+            let request = requests.recv().await.unwrap();
+            assert_eq!(&request.headers["content-range"], expected_range);
+
+            let id = request.headers["x-unique-upload-id"].clone();
+            assert_eq!(upload_id.get_or_insert_with(|| id.clone()), &id);
+
+            assert_eq!(request.field("api_key").as_deref(), Some("key"));
+            assert!(request.field("timestamp").is_some());
+            assert!(request.field("signature").is_some());
+        }
+        assert!(requests.recv().await.is_none());
+    }
+
+    #[test]
+    fn explicit_mime_type_wins_over_sniffing() {
+        let uploader = CloudinaryUploader::new(CloudinaryConfig::default(), UploadConfig::default());
+        let png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let options = UploadOptions::default();
+
+        let sniffed = UploadSource::from_stdin(png.clone(), None, None).unwrap();
+        assert_eq!(uploader.source_resource_type(&sniffed, &options), "image");
+
+        let explicit = UploadSource::from_stdin(png, None, Some("application/zip")).unwrap();
+        assert_eq!(uploader.source_resource_type(&explicit, &options), "auto");
+
+        // A type declared by a data URI is only a hint
+        let data_uri = "data:application/octet-stream;base64,iVBORw0KGgo=";
+        let declared = UploadSource::from_data_uri(data_uri, None, None).unwrap();
+        assert_eq!(uploader.source_resource_type(&declared, &options), "image");
+
+        let explicit = UploadSource::from_data_uri(data_uri, None, Some("video/mp4")).unwrap();
+        assert_eq!(uploader.source_resource_type(&explicit, &options), "video");
+    }
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn encodes_key_values() {
+        assert_eq!(encode_key_values(&pairs(&[("alt", "A cat"), ("caption", "Hi")])), "alt=A cat|caption=Hi");
+        assert_eq!(encode_key_values(&[]), "");
+    }
+
+    #[test]
+    fn escapes_separators_in_values() {
+        assert_eq!(
+            encode_key_values(&pairs(&[("formula", "a=b|c"), ("plain", "x")])),
+            "formula=a\\=b\\|c|plain=x"
+        );
+    }
+
+    fn signing_uploader() -> CloudinaryUploader {
+        let config = CloudinaryConfig {
+            cloud_name: "demo".to_string(),
+            api_key: "key".to_string(),
+            api_secret: "secret".to_string(),
+            ..Default::default()
+        };
+        CloudinaryUploader::new(config, UploadConfig::default())
+    }
+
+    #[test]
+    fn joins_tags_with_commas() {
+        let options = UploadOptions {
+            tags: vec!["summer".to_string(), "beach trip".to_string()],
+            ..Default::default()
+        };
+        let params = signing_uploader().upload_params(&options).unwrap();
+        assert!(params.contains(&("tags".to_string(), "summer,beach trip".to_string())));
+    }
+
+    #[test]
+    fn rejects_tags_containing_commas() {
+        let options = UploadOptions {
+            tags: vec!["summer".to_string(), "a,b".to_string()],
+            ..Default::default()
+        };
+        let err = signing_uploader().upload_params(&options).unwrap_err();
+        assert!(err.to_string().contains("'a,b'"));
+    }
+
+    #[test]
+    fn parses_pending_eager_placeholders() {
+        let response: UploadResponse = serde_json::from_value(serde_json::json!({
+            "public_id": "master",
+            "version": 1,
+            "signature": "abc",
+            "format": "mp4",
+            "resource_type": "video",
+            "created_at": "2024-01-01T00:00:00Z",
+            "bytes": 1024,
+            "url": "http://res.cloudinary.com/demo/video/upload/v1/master.mp4",
+            "secure_url": "https://res.cloudinary.com/demo/video/upload/v1/master.mp4",
+            "eager": [
+                { "transformation": "w_1280,c_limit/f_mp4", "secure_url": "https://example.com/e.mp4" },
+                { "status": "processing" },
+            ],
+        }))
+        .unwrap();
+
+        let eager = response.eager.unwrap();
+        assert_eq!(eager[0].transformation, "w_1280,c_limit/f_mp4");
+        assert_eq!(eager[1].transformation, "");
+        assert_eq!(eager[1].status.as_deref(), Some("processing"));
+    }
+
+    #[test]
+    fn lists_context_and_metadata_as_pairs() {
+        let response: UploadResponse = serde_json::from_value(serde_json::json!({
+            "public_id": "banner",
+            "version": 1,
+            "signature": "abc",
+            "format": "jpg",
+            "resource_type": "image",
+            "created_at": "2024-01-01T00:00:00Z",
+            "bytes": 1024,
+            "url": "http://res.cloudinary.com/demo/image/upload/v1/banner.jpg",
+            "secure_url": "https://res.cloudinary.com/demo/image/upload/v1/banner.jpg",
+            "context": { "custom": { "alt": "Spring sale" } },
+            "metadata": { "sku": "A-1001", "stock": 3 },
+        }))
+        .unwrap();
+
+        assert_eq!(response.context_pairs(), ["alt=Spring sale"]);
+        assert_eq!(response.metadata_pairs(), ["sku=A-1001", "stock=3"]);
     }
 }
@@ -0,0 +1,310 @@
+use anyhow::{Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::Local;
+use sha1::{Digest, Sha1};
+use std::fs::File;
+use std::path::{Component, Path};
+
+use super::UploadSource;
+
+/// A public ID pattern such as `blog/{date:%Y/%m}/{stem}-{sha1:8}`.
+///
+/// Supported placeholders:
+/// - `{stem}`: file name without extension
+/// - `{ext}`: file extension
+/// - `{relpath}`: path relative to the uploaded directory, without extension
+/// - `{parent}`: name of the directory containing the file
+/// - `{sha1}` / `{sha1:N}`: SHA-1 of the content, optionally truncated to N hex digits
+/// - `{date:FORMAT}`: current local date formatted with strftime syntax
+#[derive(Debug, Clone)]
+pub struct PublicIdTemplate {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Literal(String),
+    Stem,
+    Ext,
+    RelPath,
+    Parent,
+    Sha1(Option<usize>),
+    Date(String),
+}
+
+impl PublicIdTemplate {
+    /// Parse a template, rejecting unknown placeholders and invalid date formats
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+
+        while let Some(open) = rest.find('{') {
+            if open > 0 {
+                segments.push(Segment::Literal(rest[..open].to_string()));
+            }
+
+            let close = rest[open..]
+                .find('}')
+                .map(|i| open + i)
+                .with_context(|| format!("Unclosed placeholder in public ID template: {}", template))?;
+
+            let placeholder = &rest[open + 1..close];
+            let (name, arg) = match placeholder.split_once(':') {
+                Some((name, arg)) => (name, Some(arg)),
+                None => (placeholder, None),
+            };
+
+            let segment = match (name, arg) {
+                ("stem", None) => Segment::Stem,
+                ("ext", None) => Segment::Ext,
+                ("relpath", None) => Segment::RelPath,
+                ("parent", None) => Segment::Parent,
+                ("sha1", None) => Segment::Sha1(None),
+                ("sha1", Some(len)) => {
+                    let len = len
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|len| (1..=40).contains(len))
+                        .with_context(|| format!("Invalid length in {{{}}}: expected 1-40", placeholder))?;
+                    Segment::Sha1(Some(len))
+                }
+                ("date", Some(format)) => {
+                    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+                        anyhow::bail!("Invalid date format in {{{}}}", placeholder);
+                    }
+                    Segment::Date(format.to_string())
+                }
+                _ => anyhow::bail!("Unknown placeholder in public ID template: {{{}}}", placeholder),
+            };
+
+            segments.push(segment);
+            rest = &rest[close + 1..];
+        }
+
+        if !rest.is_empty() {
+            segments.push(Segment::Literal(rest.to_string()));
+        }
+
+        Ok(PublicIdTemplate { segments })
+    }
+
+    /// Render the public ID for `source`, with `{relpath}` relative to `root`
+    pub fn render(&self, source: &UploadSource, root: Option<&Path>) -> Result<String> {
+        let names = SourceNames::new(source, root);
+        let mut public_id = String::new();
+        let mut digest: Option<String> = None;
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => public_id.push_str(text),
+                Segment::Stem => public_id.push_str(&names.stem),
+                Segment::Ext => public_id.push_str(&names.ext),
+                Segment::RelPath => public_id.push_str(&names.relpath),
+                Segment::Parent => public_id.push_str(&names.parent),
+                Segment::Sha1(len) => {
+                    if digest.is_none() {
+                        digest = Some(content_sha1(source)?);
+                    }
+                    let hex = digest.as_deref().unwrap_or_default();
+                    public_id.push_str(&hex[..len.unwrap_or(hex.len())]);
+                }
+                Segment::Date(format) => {
+                    public_id.push_str(&Local::now().format(format).to_string())
+                }
+            }
+        }
+
+        Ok(public_id.trim_start_matches('/').to_string())
+    }
+}
+
+/// The name parts of a source that placeholders refer to
+struct SourceNames {
+    stem: String,
+    ext: String,
+    relpath: String,
+    parent: String,
+}
+
+impl SourceNames {
+    fn new(source: &UploadSource, root: Option<&Path>) -> Self {
+        let (path, relative) = match source {
+            UploadSource::File(path) => {
+                let relative = root
+                    .and_then(|root| path.strip_prefix(root).ok())
+                    .or_else(|| path.file_name().map(Path::new))
+                    .unwrap_or(path)
+                    .to_path_buf();
+                (path.clone(), relative)
+            }
+            UploadSource::Url(url) => {
+                let path = url::Url::parse(url)
+                    .map(|u| u.path().trim_start_matches('/').to_string())
+                    .unwrap_or_default();
+                (path.clone().into(), path.into())
+            }
+            UploadSource::Bytes { file_name, .. } => (file_name.into(), file_name.into()),
+        };
+
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let ext = path
+            .extension()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let parent = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        // Join with '/' regardless of platform and drop the extension
+        let relpath = relative
+            .with_extension("")
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        SourceNames {
+            stem,
+            ext,
+            relpath,
+            parent,
+        }
+    }
+}
+
+/// Hex SHA-1 of the content of a local file or in-memory data
+fn content_sha1(source: &UploadSource) -> Result<String> {
+    let mut hasher = Sha1::new();
+
+    match source {
+        UploadSource::File(path) => {
+            let mut file = File::open(path)
+                .with_context(|| format!("Failed to open {} for hashing", path.display()))?;
+            std::io::copy(&mut file, &mut hasher)
+                .with_context(|| format!("Failed to hash {}", path.display()))?;
+        }
+        UploadSource::Bytes { data, .. } => hasher.update(data),
+        UploadSource::Url(url) => {
+            anyhow::bail!("{{sha1}} cannot be used for remote URLs: {}", url)
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// SHA-1 of `hello world`
+    const HELLO_SHA1: &str = "2aae6c35c94fcfb415dbe95f408b9ce91ee846ed";
+
+    fn render(template: &str, source: &UploadSource, root: Option<&Path>) -> String {
+        PublicIdTemplate::parse(template)
+            .unwrap()
+            .render(source, root)
+            .unwrap()
+    }
+
+    fn bytes(file_name: &str) -> UploadSource {
+        UploadSource::from_stdin(b"hello world".to_vec(), Some(file_name), None).unwrap()
+    }
+
+    /// A fresh directory under the system temp dir, removed when dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("cld-public-id-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn renders_content_hash() {
+        let source = bytes("photo.jpg");
+        assert_eq!(render("{sha1}", &source, None), HELLO_SHA1);
+        assert_eq!(render("{stem}-{sha1:8}", &source, None), "photo-2aae6c35");
+        assert_eq!(render("{sha1:40}.{ext}", &source, None), format!("{}.jpg", HELLO_SHA1));
+    }
+
+    #[test]
+    fn renders_date() {
+        let expected = Local::now().format("%Y/%m").to_string();
+        assert_eq!(render("blog/{date:%Y/%m}/{stem}", &bytes("a.png"), None), format!("blog/{}/a", expected));
+    }
+
+    #[test]
+    fn renders_relative_path_of_files() {
+        let dir = TempDir::new("relpath");
+        let nested = dir.0.join("albums").join("2024");
+        fs::create_dir_all(&nested).unwrap();
+        let path = nested.join("beach.photo.jpg");
+        fs::write(&path, "hello world").unwrap();
+        let source = UploadSource::File(path);
+
+        assert_eq!(render("{relpath}", &source, Some(&dir.0)), "albums/2024/beach.photo");
+        assert_eq!(render("{parent}/{stem}", &source, Some(&dir.0)), "2024/beach.photo");
+        assert_eq!(render("{ext}", &source, Some(&dir.0)), "jpg");
+        assert_eq!(render("{sha1:8}", &source, Some(&dir.0)), "2aae6c35");
+
+        // Without a root the path is relative to the file's own directory
+        assert_eq!(render("{relpath}", &source, None), "beach.photo");
+    }
+
+    #[test]
+    fn strips_leading_slashes() {
+        assert_eq!(render("/{stem}", &bytes("a.png"), None), "a");
+    }
+
+    #[test]
+    fn rejects_unclosed_placeholders() {
+        let err = PublicIdTemplate::parse("{stem").unwrap_err();
+        assert!(err.to_string().contains("Unclosed placeholder"));
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        for template in ["{name}", "{stem:3}", "{date}", "{}"] {
+            let err = PublicIdTemplate::parse(template).unwrap_err();
+            assert!(err.to_string().contains("Unknown placeholder"), "{}", template);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_hash_lengths() {
+        for template in ["{sha1:0}", "{sha1:41}", "{sha1:x}"] {
+            assert!(PublicIdTemplate::parse(template).is_err(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_date_formats() {
+        assert!(PublicIdTemplate::parse("{date:%Q}").is_err());
+    }
+
+    #[test]
+    fn hash_of_remote_urls_is_an_error() {
+        let template = PublicIdTemplate::parse("{sha1}").unwrap();
+        let source = UploadSource::Url("https://example.com/a.png".to_string());
+        assert!(template.render(&source, None).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use rand::Rng;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::future::Future;
+use std::time::{Duration, SystemTime};
+
+use super::CloudinaryError;
+use crate::config::RetryConfig;
+
+/// Decides whether and when a failed upload request is attempted again
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &RetryConfig) -> Self {
+        RetryPolicy {
+            max_attempts: config.max_attempts.max(1),
+            base_delay: Duration::from_millis(config.base_delay_ms),
+            max_delay: Duration::from_millis(config.max_delay_ms),
+        }
+    }
+
+    /// Delay before the attempt following `attempt` (1-based).
+    ///
+    /// A `Retry-After` value sent by the server always wins. Otherwise the delay
+    /// doubles with every attempt, is capped at `max_delay`, and a random half of
+    /// it is dropped so that parallel uploads do not retry in lockstep.
+    pub fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after;
+        }
+
+        let exponent = attempt.saturating_sub(1).min(16);
+        let backoff = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+
+        let half = backoff / 2;
+        let jitter = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+        half + Duration::from_millis(jitter)
+    }
+
+    /// Send the request produced by `build`, retrying transient failures.
+    ///
+    /// `build` is called again for every attempt since a streamed multipart body
+    /// can only be sent once. Non-retryable responses and the last failed attempt
+    /// are turned into a [`CloudinaryError`] carrying Cloudinary's error text.
+    /// Retries are reported on `progress` when given.
+    pub async fn send<F, Fut>(
+        &self,
+        progress: Option<&ProgressBar>,
+        label: &str,
+        build: F,
+    ) -> Result<Response>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<RequestBuilder>>,
+    {
+        let max_attempts = self.max_attempts;
+        let mut attempt = 1;
+
+        loop {
+            let (reason, delay) = match build().await?.send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    let server_delay = retry_after(response.headers());
+                    let error_text = response.text().await?;
+
+                    if !is_retryable_status(status) || attempt >= max_attempts {
+                        return Err(CloudinaryError::from_response(status, &error_text))
+                            .context(format!("Request failed after {} attempt(s)", attempt));
+                    }
+                    (format!("HTTP {}", status), self.delay(attempt, server_delay))
+                }
+                Err(err) => {
+                    if !is_retryable_error(&err) || attempt >= max_attempts {
+                        return Err(err).context(format!(
+                            "Failed to send request after {} attempt(s)",
+                            attempt
+                        ));
+                    }
+                    (err.to_string(), self.delay(attempt, None))
+                }
+            };
+
+            attempt += 1;
+            if let Some(pb) = progress {
+                pb.println(format!(
+                    "Retrying {} in {:.1}s (attempt {}/{}): {}",
+                    label,
+                    delay.as_secs_f64(),
+                    attempt,
+                    max_attempts,
+                    reason
+                ));
+            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// HTTP client applying the connect and request timeouts from `config`, so
+/// that a stalled connection fails as a retryable timeout instead of hanging
+pub fn http_client(config: &RetryConfig) -> reqwest::Client {
+    let mut builder =
+        reqwest::Client::builder().connect_timeout(Duration::from_secs(config.connect_timeout_secs));
+    if config.timeout_secs > 0 {
+        builder = builder.timeout(Duration::from_secs(config.timeout_secs));
+    }
+    builder.build().expect("Failed to build HTTP client")
+}
+
+/// 5xx responses and Cloudinary's rate limit responses (420, 429) are transient
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status.as_u16() == 420 || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Connection failures and timeouts are worth another attempt
+pub fn is_retryable_error(err: &reqwest::Error) -> bool {
+    err.is_connect() || err.is_timeout()
+}
+
+/// Parse a `Retry-After` header given either in seconds or as an HTTP date
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+        }
+    }
+
+    /// Check many samples since the jitter is random
+    fn assert_delay_between(attempt: u32, min_ms: u64, max_ms: u64) {
+        for _ in 0..200 {
+            let delay = policy().delay(attempt, None);
+            assert!(
+                delay >= Duration::from_millis(min_ms) && delay <= Duration::from_millis(max_ms),
+                "delay {:?} for attempt {} outside {}ms..={}ms",
+                delay,
+                attempt,
+                min_ms,
+                max_ms
+            );
+        }
+    }
+
+    #[test]
+    fn delay_doubles_per_attempt_with_jitter() {
+        assert_delay_between(1, 50, 100);
+        assert_delay_between(2, 100, 200);
+        assert_delay_between(3, 200, 400);
+        assert_delay_between(4, 400, 800);
+    }
+
+    #[test]
+    fn delay_is_capped_at_max_delay() {
+        assert_delay_between(5, 500, 1000);
+        assert_delay_between(40, 500, 1000);
+    }
+
+    #[test]
+    fn retry_after_wins_over_backoff() {
+        let retry_after = Duration::from_secs(42);
+        assert_eq!(policy().delay(1, Some(retry_after)), retry_after);
+        assert_eq!(policy().delay(10, Some(Duration::ZERO)), Duration::ZERO);
+    }
+
+    fn headers(retry_after: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(retry_after).unwrap());
+        headers
+    }
+
+    #[test]
+    fn parses_retry_after_seconds() {
+        assert_eq!(retry_after(&headers("120")), Some(Duration::from_secs(120)));
+        assert_eq!(retry_after(&headers(" 7 ")), Some(Duration::from_secs(7)));
+    }
+
+    #[test]
+    fn parses_retry_after_http_date() {
+        let date = SystemTime::now() + Duration::from_secs(90);
+        let delay = retry_after(&headers(&httpdate::fmt_http_date(date))).unwrap();
+        // HTTP dates have a resolution of one second
+        assert!(delay > Duration::from_secs(88) && delay <= Duration::from_secs(90));
+
+        let past = httpdate::fmt_http_date(SystemTime::now() - Duration::from_secs(60));
+        assert_eq!(retry_after(&headers(&past)), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn ignores_missing_or_invalid_retry_after() {
+        assert_eq!(retry_after(&HeaderMap::new()), None);
+        assert_eq!(retry_after(&headers("soon")), None);
+    }
+
+    #[test]
+    fn retries_rate_limits_and_server_errors() {
+        for code in [420, 429, 500, 502, 503, 504] {
+            assert!(is_retryable_status(StatusCode::from_u16(code).unwrap()), "{}", code);
+        }
+    }
+
+    #[test]
+    fn never_retries_rejected_requests() {
+        for code in [400, 401, 403, 404, 413] {
+            assert!(!is_retryable_status(StatusCode::from_u16(code).unwrap()), "{}", code);
+        }
+    }
+}
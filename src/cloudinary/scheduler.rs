@@ -0,0 +1,57 @@
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Limits how many uploads run at the same time.
+///
+/// Every upload holds a slot of the overall limit while it runs. Videos also
+/// hold a slot of the separate video limit, which is usually lower since each
+/// video upload keeps a connection busy for much longer.
+#[derive(Clone)]
+pub struct UploadScheduler {
+    uploads: Arc<Semaphore>,
+    videos: Arc<Semaphore>,
+}
+
+/// Slots held by a running upload, released when dropped
+pub struct UploadSlot {
+    _upload: OwnedSemaphorePermit,
+    _video: Option<OwnedSemaphorePermit>,
+}
+
+impl UploadScheduler {
+    pub fn new(concurrency: usize, video_concurrency: usize) -> Self {
+        UploadScheduler {
+            uploads: Arc::new(Semaphore::new(concurrency.max(1))),
+            videos: Arc::new(Semaphore::new(video_concurrency.max(1))),
+        }
+    }
+
+    /// Wait until an upload of the given resource type may start
+    pub async fn acquire(&self, resource_type: &str) -> Result<UploadSlot> {
+        // Take the video slot first so waiting videos don't hold general slots
+        let video = if resource_type == "video" {
+            Some(
+                self.videos
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .context("Upload scheduler closed")?,
+            )
+        } else {
+            None
+        };
+
+        let upload = self
+            .uploads
+            .clone()
+            .acquire_owned()
+            .await
+            .context("Upload scheduler closed")?;
+
+        Ok(UploadSlot {
+            _upload: upload,
+            _video: video,
+        })
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::{Context, Result};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::{CloudinaryConfig, SignatureAlgorithm};
+
+/// Parameters that are sent with a request but never part of its signature
+const UNSIGNED_PARAMS: &[&str] = &["file", "api_key", "resource_type", "cloud_name", "signature"];
+
+/// Request parameters keyed by name, kept in the order Cloudinary signs them
+pub type Params = BTreeMap<String, ParamValue>;
+
+/// The value of a request parameter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamValue {
+    Single(String),
+    /// Sent as repeated `name[]` fields and signed as a comma-separated list
+    List(Vec<String>),
+}
+
+impl ParamValue {
+    fn is_empty(&self) -> bool {
+        match self {
+            ParamValue::Single(value) => value.is_empty(),
+            ParamValue::List(values) => values.iter().all(String::is_empty),
+        }
+    }
+
+    fn signed_value(&self) -> String {
+        match self {
+            ParamValue::Single(value) => value.clone(),
+            ParamValue::List(values) => values
+                .iter()
+                .filter(|value| !value.is_empty())
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+impl From<String> for ParamValue {
+    fn from(value: String) -> Self {
+        ParamValue::Single(value)
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(value: &str) -> Self {
+        ParamValue::Single(value.to_string())
+    }
+}
+
+impl From<Vec<String>> for ParamValue {
+    fn from(values: Vec<String>) -> Self {
+        ParamValue::List(values)
+    }
+}
+
+/// The `key=value&key=value` string that is hashed together with the API secret.
+///
+/// Parameters are sorted by name, empty values are left out and list values
+/// are joined with commas. `file`, `api_key`, `resource_type`, `cloud_name`
+/// and `signature` itself are never signed.
+pub fn string_to_sign(params: &Params) -> String {
+    params
+        .iter()
+        .filter(|(key, value)| !UNSIGNED_PARAMS.contains(&key.as_str()) && !value.is_empty())
+        .map(|(key, value)| format!("{}={}", key, value.signed_value()))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Hex digest of the string to sign followed by the API secret
+pub fn sign(params: &Params, api_secret: &str, algorithm: SignatureAlgorithm) -> String {
+    let payload = format!("{}{}", string_to_sign(params), api_secret);
+    match algorithm {
+        SignatureAlgorithm::Sha1 => format!("{:x}", Sha1::digest(payload.as_bytes())),
+        SignatureAlgorithm::Sha256 => format!("{:x}", Sha256::digest(payload.as_bytes())),
+    }
+}
+
+/// Form fields of a signed request: `params` plus the timestamp, the
+/// signature and the API key
+pub fn signed_fields(mut params: Params, config: &CloudinaryConfig) -> Result<Vec<(String, String)>> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("Failed to get system time")?
+        .as_secs()
+        .to_string();
+    params.insert("timestamp".to_string(), timestamp.into());
+
+    let signature = sign(&params, &config.api_secret, config.signature_algorithm);
+
+    let mut fields = form_fields(params);
+    fields.push(("signature".to_string(), signature));
+    fields.push(("api_key".to_string(), config.api_key.clone()));
+
+    Ok(fields)
+}
+
+/// Flatten parameters into form fields, expanding lists into `name[]` entries
+pub fn form_fields(params: Params) -> Vec<(String, String)> {
+    let mut fields = Vec::with_capacity(params.len());
+    for (key, value) in params {
+        match value {
+            ParamValue::Single(value) => fields.push((key, value)),
+            ParamValue::List(values) => {
+                let name = format!("{}[]", key);
+                fields.extend(values.into_iter().map(|value| (name.clone(), value)));
+            }
+        }
+    }
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(pairs: &[(&str, &str)]) -> Params {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), ParamValue::from(*value)))
+            .collect()
+    }
+
+    /// The example from Cloudinary's signature documentation
+    fn documented_params() -> Params {
+        params(&[
+            ("timestamp", "1315060510"),
+            ("public_id", "sample_image"),
+            ("eager", "w_400,h_300,c_pad|w_260,h_200,c_crop"),
+        ])
+    }
+
+    #[test]
+    fn signs_documented_example_with_sha1() {
+        assert_eq!(
+            sign(&documented_params(), "abcd", SignatureAlgorithm::Sha1),
+            "bfd09f95f331f558cbd1320e67aa8d488770583e"
+        );
+    }
+
+    #[test]
+    fn signs_documented_example_with_sha256() {
+        assert_eq!(
+            sign(&documented_params(), "abcd", SignatureAlgorithm::Sha256),
+            "cc927e1290f9e3ae4c1a741eda21a4630b4ce80f9ce0bc0296337d25cf40f91e"
+        );
+    }
+
+    #[test]
+    fn sorts_keys() {
+        let params = params(&[("timestamp", "1"), ("folder", "a"), ("public_id", "b")]);
+        assert_eq!(string_to_sign(&params), "folder=a&public_id=b&timestamp=1");
+    }
+
+    #[test]
+    fn leaves_out_unsigned_params() {
+        let params = params(&[
+            ("file", "@image.png"),
+            ("api_key", "key"),
+            ("resource_type", "image"),
+            ("cloud_name", "demo"),
+            ("signature", "abc"),
+            ("timestamp", "1"),
+        ]);
+        assert_eq!(string_to_sign(&params), "timestamp=1");
+    }
+
+    #[test]
+    fn drops_empty_values() {
+        let mut params = params(&[("folder", ""), ("timestamp", "1")]);
+        params.insert("tags".to_string(), ParamValue::List(Vec::new()));
+        params.insert("public_ids".to_string(), ParamValue::List(vec![String::new()]));
+        assert_eq!(string_to_sign(&params), "timestamp=1");
+    }
+
+    #[test]
+    fn joins_lists_when_signing_and_expands_them_into_fields() {
+        let mut params = params(&[("command", "add")]);
+        params.insert(
+            "public_ids".to_string(),
+            ParamValue::from(vec!["a".to_string(), "b".to_string()]),
+        );
+
+        assert_eq!(string_to_sign(&params), "command=add&public_ids=a,b");
+        assert_eq!(
+            form_fields(params),
+            vec![
+                ("command".to_string(), "add".to_string()),
+                ("public_ids[]".to_string(), "a".to_string()),
+                ("public_ids[]".to_string(), "b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn signed_fields_append_signature_and_api_key() {
+        let config = CloudinaryConfig {
+            api_key: "key".to_string(),
+            api_secret: "abcd".to_string(),
+            ..Default::default()
+        };
+
+        let fields = signed_fields(params(&[("public_id", "sample")]), &config).unwrap();
+        let timestamp = &fields.iter().find(|(key, _)| key == "timestamp").unwrap().1;
+        let expected = sign(
+            &params(&[("public_id", "sample"), ("timestamp", timestamp)]),
+            "abcd",
+            SignatureAlgorithm::Sha1,
+        );
+
+        assert_eq!(fields[fields.len() - 2], ("signature".to_string(), expected));
+        assert_eq!(fields[fields.len() - 1], ("api_key".to_string(), "key".to_string()));
+    }
+}
@@ -0,0 +1,244 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use super::source::mime_resource_type;
+
+/// Bytes read from the start of a file to recognise its format
+const HEADER_LEN: usize = 512;
+
+/// Resource type of a local file judged by its leading bytes
+pub fn sniff_file(path: &Path) -> Option<&'static str> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    File::open(path)
+        .ok()?
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+    let hint = mime_guess::from_path(path)
+        .first()
+        .and_then(|mime| mime_hint(mime.essence_str()));
+    sniff_resource_type(&header, hint)
+}
+
+/// Resource type suggested by a MIME type guessed from a file name, or `None`
+/// when the name says nothing about the content
+pub fn mime_hint(mime_type: &str) -> Option<&'static str> {
+    if mime_type == "application/octet-stream" {
+        None
+    } else {
+        Some(mime_resource_type(mime_type))
+    }
+}
+
+/// Recognise common image, video and audio formats by their magic bytes.
+///
+/// Audio is uploaded as `video`, which is how Cloudinary stores it, and PDFs
+/// are images so that pages can be transformed. Returns `None` for anything
+/// unrecognised so the caller can fall back to the file extension.
+///
+/// MPEG audio and transport streams have no real magic number, only sync
+/// bytes that plenty of other data contains too, so they are only recognised
+/// when `hint` (the resource type implied by the extension) is unknown or
+/// agrees.
+pub fn sniff_resource_type(header: &[u8], hint: Option<&str>) -> Option<&'static str> {
+    let image = Some("image");
+    let video = Some("video");
+    let weak_video = hint.map_or(true, |hint| hint == "video");
+
+    match header {
+        [0x89, b'P', b'N', b'G', ..] => image,
+        [0xFF, 0xD8, 0xFF, ..] => image,
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => image,
+        [b'B', b'M', ..] if is_bmp(header) => image,
+        [b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => image,
+        [0x00, 0x00, 0x01, 0x00, ..] if is_ico(header) => image,
+        [b'8', b'B', b'P', b'S', ..] => image,
+        [b'%', b'P', b'D', b'F', ..] => image,
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => image,
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'A', b'V', b'I', b' ', ..] => video,
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'A', b'V', b'E', ..] => video,
+        [b'F', b'O', b'R', b'M', _, _, _, _, b'A', b'I', b'F', b'F' | b'C', ..] => video,
+        [_, _, _, _, b'f', b't', b'y', b'p', brand @ ..] => iso_media_type(brand),
+        [0x1A, 0x45, 0xDF, 0xA3, ..] => video,
+        [b'F', b'L', b'V', 0x01, ..] => video,
+        [b'O', b'g', b'g', b'S', ..] => video,
+        [b'f', b'L', b'a', b'C', ..] => video,
+        [b'I', b'D', b'3', ..] => video,
+        // MPEG audio and ADTS AAC frames start with an 11-bit sync word
+        [0xFF, second, ..] if second & 0xE0 == 0xE0 && weak_video => video,
+        // MPEG transport stream packets start with 0x47 every 188 bytes
+        [0x47, ..] if header.get(188) == Some(&0x47) && weak_video => video,
+        _ if is_svg(header) => image,
+        _ => None,
+    }
+}
+
+fn u16_le(header: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(header.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_le(header: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(header.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// A `BM` file header followed by a DIB header of one of the known sizes
+fn is_bmp(header: &[u8]) -> bool {
+    matches!(u32_le(header, 14), Some(12 | 16 | 40 | 52 | 56 | 64 | 108 | 124))
+}
+
+/// An icon directory with at least one plausible entry
+fn is_ico(header: &[u8]) -> bool {
+    let count = match u16_le(header, 4) {
+        Some(count) if count > 0 => count,
+        _ => return false,
+    };
+    // First directory entry: reserved byte, colour planes, bits per pixel,
+    // image size and an image offset past the directory
+    let reserved = header.get(9);
+    let planes = u16_le(header, 10);
+    let bits = u16_le(header, 12);
+    let size = u32_le(header, 14);
+    let offset = u32_le(header, 18);
+
+    reserved == Some(&0)
+        && matches!(planes, Some(0 | 1))
+        && matches!(bits, Some(0 | 1 | 4 | 8 | 16 | 24 | 32))
+        && size.map_or(false, |size| size > 0)
+        && offset.map_or(false, |offset| offset >= 6 + 16 * u32::from(count))
+}
+
+/// ISO base media files (MP4, MOV, M4A, HEIC, AVIF) share the `ftyp` box;
+/// the major brand tells still images apart from video and audio
+fn iso_media_type(brand: &[u8]) -> Option<&'static str> {
+    match brand.get(..4)? {
+        b"avif" | b"avis" | b"heic" | b"heix" | b"heim" | b"heis" | b"mif1" | b"msf1" => Some("image"),
+        _ => Some("video"),
+    }
+}
+
+fn is_svg(header: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(header);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    (text.starts_with("<svg") || text.starts_with("<?xml")) && text.contains("<svg")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sniff(header: &[u8]) -> Option<&'static str> {
+        sniff_resource_type(header, None)
+    }
+
+    fn ftyp(brand: &[u8; 4]) -> Vec<u8> {
+        let mut header = vec![0x00, 0x00, 0x00, 0x20];
+        header.extend_from_slice(b"ftyp");
+        header.extend_from_slice(brand);
+        header.extend_from_slice(&[0x00; 20]);
+        header
+    }
+
+    #[test]
+    fn recognises_images() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("image"));
+        assert_eq!(sniff(b"\xFF\xD8\xFF\xE0\0\x10JFIF"), Some("image"));
+        assert_eq!(sniff(b"RIFF\x24\0\0\0WEBPVP8 "), Some("image"));
+        assert_eq!(sniff(b"GIF89a\x01\0\x01\0"), Some("image"));
+    }
+
+    #[test]
+    fn recognises_pdf_as_image() {
+        assert_eq!(sniff(b"%PDF-1.7\n"), Some("image"));
+    }
+
+    #[test]
+    fn recognises_svg() {
+        assert_eq!(sniff(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), Some("image"));
+        assert_eq!(
+            sniff(b"\xEF\xBB\xBF<?xml version=\"1.0\"?>\n<svg></svg>"),
+            Some("image")
+        );
+        assert_eq!(sniff(b"<?xml version=\"1.0\"?><feed/>"), None);
+    }
+
+    #[test]
+    fn tells_iso_media_brands_apart() {
+        assert_eq!(sniff(&ftyp(b"isom")), Some("video"));
+        assert_eq!(sniff(&ftyp(b"mp42")), Some("video"));
+        assert_eq!(sniff(&ftyp(b"qt  ")), Some("video"));
+        assert_eq!(sniff(&ftyp(b"M4A ")), Some("video"));
+        assert_eq!(sniff(&ftyp(b"avif")), Some("image"));
+        assert_eq!(sniff(&ftyp(b"heic")), Some("image"));
+        assert_eq!(sniff(&ftyp(b"mif1")), Some("image"));
+    }
+
+    #[test]
+    fn recognises_mp3_as_video() {
+        assert_eq!(sniff(b"ID3\x04\0\0\0\0\0\0"), Some("video"));
+        // MPEG-1 Layer III frame header without an ID3 tag
+        assert_eq!(sniff(b"\xFF\xFB\x90\x64\0\0"), Some("video"));
+    }
+
+    #[test]
+    fn leaves_unknown_headers_to_the_caller() {
+        assert_eq!(sniff(b""), None);
+        assert_eq!(sniff(b"PK\x03\x04\x14\0\0\0"), None);
+        assert_eq!(sniff(b"plain text"), None);
+    }
+
+    #[test]
+    fn validates_bmp_headers() {
+        let mut bmp = b"BM\x36\x00\x0c\x00\0\0\0\0\x36\0\0\0".to_vec();
+        bmp.extend_from_slice(&40u32.to_le_bytes());
+        bmp.extend_from_slice(&[0; 32]);
+        assert_eq!(sniff(&bmp), Some("image"));
+
+        assert_eq!(sniff(b"BMW,320i,2019,blue\nAudi,A4,2020,red\n"), None);
+        assert_eq!(sniff(b"BM"), None);
+    }
+
+    #[test]
+    fn validates_ico_headers() {
+        // One 16x16 32-bit entry of 1128 bytes stored right after the directory
+        let ico = b"\0\0\x01\0\x01\0\x10\x10\0\0\x01\0\x20\0\x68\x04\0\0\x16\0\0\0";
+        assert_eq!(sniff(ico), Some("image"));
+
+        assert_eq!(sniff(b"\0\0\x01\0\0\0"), None);
+        assert_eq!(sniff(b"\0\0\x01\0\x01\0\x10\x10\0\0\x07\0\x20\0\x68\x04\0\0\x16\0\0\0"), None);
+        assert_eq!(sniff(b"\0\0\x01\0\x01\0\x10\x10\0\0\x01\0\x20\0\x68\x04\0\0\x02\0\0\0"), None);
+        assert_eq!(sniff(&[0, 0, 1, 0, 0xAB, 0xCD, 0xEF, 0x12]), None);
+    }
+
+    #[test]
+    fn trusts_mpeg_sync_words_only_without_a_conflicting_extension() {
+        let mp3 = b"\xFF\xFB\x90\x64\0\0";
+        assert_eq!(sniff_resource_type(mp3, None), Some("video"));
+        assert_eq!(sniff_resource_type(mp3, Some("video")), Some("video"));
+        assert_eq!(sniff_resource_type(mp3, Some("auto")), None);
+        assert_eq!(sniff_resource_type(mp3, Some("image")), None);
+    }
+
+    #[test]
+    fn trusts_transport_stream_sync_bytes_only_without_a_conflicting_extension() {
+        let mut ts = vec![0u8; 189];
+        ts[0] = 0x47;
+        ts[188] = 0x47;
+        assert_eq!(sniff_resource_type(&ts, None), Some("video"));
+        assert_eq!(sniff_resource_type(&ts, Some("video")), Some("video"));
+        assert_eq!(sniff_resource_type(&ts, Some("auto")), None);
+    }
+
+    #[test]
+    fn strong_signatures_win_over_the_extension() {
+        assert_eq!(sniff_resource_type(b"\x89PNG\r\n\x1a\n", Some("video")), Some("image"));
+        assert_eq!(sniff_resource_type(b"ID3\x04\0\0", Some("auto")), Some("video"));
+    }
+
+    #[test]
+    fn ignores_generic_mime_types_as_hints() {
+        assert_eq!(mime_hint("application/octet-stream"), None);
+        assert_eq!(mime_hint("text/csv"), Some("auto"));
+        assert_eq!(mime_hint("audio/mpeg"), Some("video"));
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use bytes::Bytes;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Something that can be uploaded to Cloudinary
+#[derive(Debug, Clone)]
+pub enum UploadSource {
+    /// A file on the local disk
+    File(PathBuf),
+    /// An http(s) URL that Cloudinary fetches itself
+    Url(String),
+    /// Data held in memory, read from stdin or decoded from a data URI
+    Bytes {
+        data: Bytes,
+        file_name: String,
+        mime_type: String,
+        /// Whether `mime_type` was given with `--mime` rather than guessed
+        mime_explicit: bool,
+    },
+}
+
+impl UploadSource {
+    /// Interpret a PATH argument, treating http(s) URLs as remote sources and
+    /// decoding base64 data URIs
+    pub fn parse(value: &str, file_name: Option<&str>, mime_type: Option<&str>) -> Result<Self> {
+        if is_remote_url(value) {
+            Ok(UploadSource::Url(value.to_string()))
+        } else if is_data_uri(value) {
+            Self::from_data_uri(value, file_name, mime_type)
+        } else {
+            Ok(UploadSource::File(PathBuf::from(value)))
+        }
+    }
+
+    /// Data piped in on stdin, which may itself be a data URI
+    pub fn from_stdin(data: Vec<u8>, file_name: Option<&str>, mime_type: Option<&str>) -> Result<Self> {
+        if data.starts_with(b"data:") {
+            let text = String::from_utf8(data).context("Data URI on stdin is not valid UTF-8")?;
+            return Self::from_data_uri(text.trim(), file_name, mime_type);
+        }
+
+        Ok(Self::from_bytes(data, "stdin", file_name, mime_type, mime_type.is_some()))
+    }
+
+    /// Decode a `data:<mime>;base64,<data>` URI
+    pub fn from_data_uri(uri: &str, file_name: Option<&str>, mime_type: Option<&str>) -> Result<Self> {
+        let (header, payload) = uri
+            .strip_prefix("data:")
+            .and_then(|rest| rest.split_once(','))
+            .context("Invalid data URI: missing ',' separator")?;
+
+        let header_mime = match header.strip_suffix(";base64") {
+            Some(mime) => mime,
+            None => anyhow::bail!("Only base64 encoded data URIs are supported"),
+        };
+
+        let data = STANDARD
+            .decode(payload.trim())
+            .context("Invalid base64 payload in data URI")?;
+
+        let mime_explicit = mime_type.is_some();
+        let mime_type = mime_type.or(Some(header_mime).filter(|m| !m.is_empty()));
+        Ok(Self::from_bytes(data, "data", file_name, mime_type, mime_explicit))
+    }
+
+    /// Wrap in-memory data, filling in the file name and MIME type from each other
+    fn from_bytes(
+        data: Vec<u8>,
+        default_stem: &str,
+        file_name: Option<&str>,
+        mime_type: Option<&str>,
+        mime_explicit: bool,
+    ) -> Self {
+        let mime_type = mime_type
+            .map(str::to_string)
+            .or_else(|| file_name.and_then(|name| mime_guess::from_path(name).first()).map(|m| m.to_string()))
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+
+        let file_name = file_name.map(str::to_string).unwrap_or_else(|| {
+            match mime_guess::get_mime_extensions_str(&mime_type).and_then(|exts| exts.first()) {
+                Some(ext) => format!("{}.{}", default_stem, ext),
+                None => default_stem.to_string(),
+            }
+        });
+
+        UploadSource::Bytes {
+            data: Bytes::from(data),
+            file_name,
+            mime_type,
+            mime_explicit,
+        }
+    }
+}
+
+impl fmt::Display for UploadSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UploadSource::File(path) => write!(f, "{}", path.display()),
+            UploadSource::Url(url) => write!(f, "{}", url),
+            UploadSource::Bytes { file_name, .. } => write!(f, "{}", file_name),
+        }
+    }
+}
+
+pub fn is_remote_url(value: &str) -> bool {
+    let lower = value.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+pub fn is_data_uri(value: &str) -> bool {
+    value.starts_with("data:")
+}
+
+/// Guess the resource type of a remote file from the extension in its URL path
+pub fn remote_resource_type(url: &str) -> &'static str {
+    let path = url::Url::parse(url)
+        .map(|u| u.path().to_string())
+        .unwrap_or_default();
+
+    let mime = mime_guess::from_path(&path).first_or_octet_stream();
+    mime_resource_type(mime.essence_str())
+}
+
+/// Map a MIME type onto the Cloudinary resource type of its upload endpoint
+pub fn mime_resource_type(mime_type: &str) -> &'static str {
+    match mime_type.split('/').next().unwrap_or_default() {
+        "image" => "image",
+        // Cloudinary stores audio as video
+        "video" | "audio" => "video",
+        _ if mime_type == "application/pdf" => "image",
+        _ => "auto",
+    }
+}
@@ -0,0 +1,348 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A Cloudinary transformation such as `w_800,c_fill,q_auto/f_auto`.
+///
+/// Components are separated by `/` and applied one after another; the
+/// parameters inside a component are separated by `,`. Parameters are
+/// validated when parsing, so a typo is reported before anything is uploaded
+/// instead of being silently dropped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transformation {
+    components: Vec<Component>,
+}
+
+/// One `/`-separated step of a chained transformation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Component {
+    Params(Vec<Param>),
+    /// Passed through verbatim, written as `raw:<component>`
+    Raw(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Param {
+    Width(String),
+    Height(String),
+    Crop(String),
+    Gravity(String),
+    Quality(String),
+    Format(String),
+    Effect(String),
+    /// Any other documented parameter, e.g. `a_90` or `t_thumbnail`
+    Other(String, String),
+}
+
+const CROP_MODES: &[&str] = &[
+    "scale", "fit", "limit", "mfit", "fill", "lfill", "fill_pad", "pad", "lpad", "mpad", "crop",
+    "thumb", "auto", "auto_pad", "imagga_crop", "imagga_scale",
+];
+
+const GRAVITIES: &[&str] = &[
+    "north_west", "north", "north_east", "west", "center", "east", "south_west", "south",
+    "south_east", "xy_center", "face", "faces", "face:center", "faces:center", "face:auto",
+    "faces:auto", "body", "body:face", "auto", "custom", "custom:face", "custom:faces",
+    "custom:adv_face", "custom:adv_faces", "adv_face", "adv_faces", "adv_eyes", "ocr_text",
+];
+
+const FORMATS: &[&str] = &[
+    "auto", "avif", "bmp", "gif", "heic", "ico", "jpg", "jpeg", "jxl", "pdf", "png", "svg",
+    "tiff", "webp", "mp4", "webm", "mov", "ogv", "m3u8", "mpd", "mp3", "ogg", "wav", "aac",
+    "flac", "m4a",
+];
+
+const QUALITY_PRESETS: &[&str] = &["auto", "auto:best", "auto:good", "auto:eco", "auto:low"];
+
+/// Parameters passed through without checking their value
+const OTHER_KEYS: &[&str] = &[
+    "a", "ac", "af", "ar", "b", "bo", "br", "co", "cs", "d", "dl", "dn", "dpr", "du", "eo", "fl",
+    "fn", "fps", "if", "ki", "l", "o", "pg", "r", "so", "sp", "t", "u", "vc", "vs", "x", "y", "z",
+];
+
+impl Transformation {
+    /// The transformation in the form accepted by `from_str`, keeping the
+    /// `raw:` marker on passthrough components
+    pub fn to_spec(&self) -> String {
+        self.components
+            .iter()
+            .map(|component| match component {
+                Component::Raw(raw) => format!("raw:{}", raw),
+                params => params.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Whether the transformation only converts to a concrete format, like `f_webp`
+    pub fn format_only(&self) -> Option<&str> {
+        match self.components.as_slice() {
+            [Component::Params(params)] => match params.as_slice() {
+                [Param::Format(format)] if format != "auto" => Some(format),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Reject parameters that are only resolved when an asset is delivered,
+    /// such as `f_auto` or `w_auto`, since Cloudinary refuses them at upload time
+    pub fn check_incoming(&self) -> Result<()> {
+        for component in &self.components {
+            let Component::Params(params) = component else {
+                continue;
+            };
+            for param in params {
+                let delivery_only = match param {
+                    Param::Format(value) => value == "auto",
+                    Param::Width(value) | Param::Height(value) => value == "auto" || value.starts_with("auto:"),
+                    Param::Other(key, value) => key == "dpr" && value == "auto",
+                    _ => false,
+                };
+                if delivery_only {
+                    anyhow::bail!("'{}' can only be used in delivery URLs, not when uploading", param);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Transformation {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        if value.is_empty() {
+            anyhow::bail!("Transformation is empty");
+        }
+
+        // A bare format name such as `webp` is shorthand for `f_webp`
+        if FORMATS.contains(&value) {
+            return Ok(Transformation {
+                components: vec![Component::Params(vec![Param::Format(value.to_string())])],
+            });
+        }
+
+        let components = value
+            .split('/')
+            .map(parse_component)
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Invalid transformation: {}", value))?;
+
+        Ok(Transformation { components })
+    }
+}
+
+fn parse_component(component: &str) -> Result<Component> {
+    if let Some(raw) = component.strip_prefix("raw:") {
+        return Ok(Component::Raw(raw.to_string()));
+    }
+
+    if component.is_empty() {
+        anyhow::bail!("empty transformation component");
+    }
+
+    component.split(',').map(parse_param).collect::<Result<Vec<_>>>().map(Component::Params)
+}
+
+fn parse_param(param: &str) -> Result<Param> {
+    let (key, value) = param
+        .split_once('_')
+        .filter(|(_, value)| !value.is_empty())
+        .with_context(|| format!("'{}' is not a key_value parameter", param))?;
+
+    let value = value.to_string();
+    let param = match key {
+        "w" | "h" => {
+            if !is_dimension(&value) {
+                anyhow::bail!("invalid {} '{}': expected a number or 'auto'", key, value);
+            }
+            if key == "w" {
+                Param::Width(value)
+            } else {
+                Param::Height(value)
+            }
+        }
+        "c" => {
+            if !CROP_MODES.contains(&value.as_str()) {
+                anyhow::bail!("unknown crop mode '{}'", value);
+            }
+            Param::Crop(value)
+        }
+        "g" => {
+            let known = GRAVITIES.contains(&value.as_str()) || value.starts_with("auto:");
+            if !known {
+                anyhow::bail!("unknown gravity '{}'", value);
+            }
+            Param::Gravity(value)
+        }
+        "q" => {
+            let percent = value.parse::<u8>().map(|q| (1..=100).contains(&q)).unwrap_or(false);
+            if !percent && !QUALITY_PRESETS.contains(&value.as_str()) {
+                anyhow::bail!("invalid quality '{}': expected 1-100 or auto[:best|good|eco|low]", value);
+            }
+            Param::Quality(value)
+        }
+        "f" => {
+            if !FORMATS.contains(&value.as_str()) {
+                anyhow::bail!("unknown format '{}'", value);
+            }
+            Param::Format(value)
+        }
+        "e" => {
+            let name = value.split(':').next().unwrap_or_default();
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                anyhow::bail!("invalid effect '{}'", value);
+            }
+            Param::Effect(value)
+        }
+        _ if OTHER_KEYS.contains(&key) || key.starts_with('$') => Param::Other(key.to_string(), value),
+        _ => anyhow::bail!("unknown transformation parameter '{}'", key),
+    };
+
+    Ok(param)
+}
+
+fn is_dimension(value: &str) -> bool {
+    value == "auto"
+        || value.starts_with("auto:")
+        || value.starts_with('$')
+        || value.parse::<f64>().map(|v| v > 0.0).unwrap_or(false)
+}
+
+impl fmt::Display for Transformation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let components = self
+            .components
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("/");
+        write!(f, "{}", components)
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Component::Raw(raw) => write!(f, "{}", raw),
+            Component::Params(params) => {
+                let params = params.iter().map(ToString::to_string).collect::<Vec<_>>().join(",");
+                write!(f, "{}", params)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Param::Width(v) => write!(f, "w_{}", v),
+            Param::Height(v) => write!(f, "h_{}", v),
+            Param::Crop(v) => write!(f, "c_{}", v),
+            Param::Gravity(v) => write!(f, "g_{}", v),
+            Param::Quality(v) => write!(f, "q_{}", v),
+            Param::Format(v) => write!(f, "f_{}", v),
+            Param::Effect(v) => write!(f, "e_{}", v),
+            Param::Other(key, v) => write!(f, "{}_{}", key, v),
+        }
+    }
+}
+
+impl Serialize for Transformation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_spec())
+    }
+}
+
+impl<'de> Deserialize<'de> for Transformation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(value: &str) -> Result<Transformation> {
+        value.parse()
+    }
+
+    #[test]
+    fn round_trips_chained_components() {
+        let transformation = parse("w_800,c_fill,q_auto/f_auto").unwrap();
+        assert_eq!(transformation.to_string(), "w_800,c_fill,q_auto/f_auto");
+        assert_eq!(transformation.to_spec(), "w_800,c_fill,q_auto/f_auto");
+        assert_eq!(parse(&transformation.to_spec()).unwrap(), transformation);
+        assert_eq!(transformation.format_only(), None);
+    }
+
+    #[test]
+    fn expands_bare_format_names() {
+        let transformation = parse("webp").unwrap();
+        assert_eq!(transformation.to_string(), "f_webp");
+        assert_eq!(transformation.format_only(), Some("webp"));
+        assert_eq!(parse("f_webp").unwrap(), transformation);
+    }
+
+    #[test]
+    fn passes_raw_components_through() {
+        let transformation = parse("w_100/raw:l_text:Arial_40:Hello,co_red").unwrap();
+        assert_eq!(transformation.to_string(), "w_100/l_text:Arial_40:Hello,co_red");
+        assert_eq!(transformation.to_spec(), "w_100/raw:l_text:Arial_40:Hello,co_red");
+        assert_eq!(parse(&transformation.to_spec()).unwrap(), transformation);
+    }
+
+    #[test]
+    fn accepts_documented_parameters() {
+        assert!(parse("a_90,t_thumbnail,$w_200").is_ok());
+        assert!(parse("g_auto:subject,q_auto:eco,e_sepia:50").is_ok());
+        assert!(parse("w_0.5,h_auto").is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_values() {
+        for value in ["c_bogus", "q_0", "q_101", "g_nowhere", "f_xyz", "w_-10", "e_"] {
+            assert!(parse(value).is_err(), "{} should be rejected", value);
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_parameters() {
+        let err = parse("zz_1").unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown transformation parameter 'zz'"));
+        assert!(parse("w800").is_err());
+    }
+
+    #[test]
+    fn rejects_empty_components() {
+        for value in ["", "  ", "w_100//f_auto", "w_100/", "/w_100", "w_100,"] {
+            assert!(parse(value).is_err(), "{:?} should be rejected", value);
+        }
+    }
+
+    #[test]
+    fn auto_format_is_not_a_format_conversion() {
+        assert_eq!(parse("auto").unwrap().format_only(), None);
+        assert_eq!(parse("f_auto").unwrap().format_only(), None);
+    }
+
+    #[test]
+    fn rejects_delivery_only_parameters_at_upload() {
+        for value in ["auto", "f_auto", "w_800,c_fill/f_auto", "w_auto,c_scale", "h_auto:100", "dpr_auto"] {
+            let err = parse(value).unwrap().check_incoming().unwrap_err();
+            assert!(err.to_string().contains("only be used in delivery URLs"), "{}", value);
+        }
+    }
+
+    #[test]
+    fn accepts_storable_parameters_at_upload() {
+        for value in ["webp", "w_800,c_fill,q_auto/f_webp", "g_auto,c_fill,w_200", "dpr_2.0", "raw:f_auto"] {
+            assert!(parse(value).unwrap().check_incoming().is_ok(), "{}", value);
+        }
+    }
+}
@@ -11,12 +11,41 @@ pub fn execute() -> Result<()> {
             println!("  {}: {}", "Cloud Name".cyan(), config.cloudinary.cloud_name);
             println!("  {}: {}", "API Key".cyan(), config.cloudinary.api_key);
             println!("  {}: {}", "API Secret".cyan(), mask_secret(&config.cloudinary.api_secret));
+            if let Some(preset) = &config.cloudinary.upload_preset {
+                println!("  {}: {}", "Upload Preset".cyan(), preset);
+            }
             
             if !config.cloudinary.default_folder.is_empty() {
                 println!("  {}: {}", "Default Folder".cyan(), config.cloudinary.default_folder);
             } else {
                 println!("  {}: {}", "Default Folder".cyan(), "(not set)".dimmed());
             }
+
+            println!("  {}: {}", "API URL".cyan(), config.cloudinary.api_url());
+            println!("  {}: {}", "Delivery URL".cyan(), config.cloudinary.delivery_url());
+            println!("  {}: {}", "Signature Algorithm".cyan(), config.cloudinary.signature_algorithm);
+            if let Some(key) = &config.cloudinary.auth_token_key {
+                println!("  {}: {}", "Auth Token Key".cyan(), mask_secret(key));
+            }
+
+            print_info("Upload Settings:");
+            println!("  {}: {} bytes", "Chunk Threshold".cyan(), config.upload.chunk_threshold);
+            println!("  {}: {} bytes", "Chunk Size".cyan(), config.upload.chunk_size);
+            println!("  {}: {}", "Concurrency".cyan(), config.upload.concurrency);
+            println!("  {}: {}", "Video Concurrency".cyan(), config.upload.video_concurrency);
+            println!("  {}: {}", "Retry Attempts".cyan(), config.upload.retry.max_attempts);
+            println!(
+                "  {}: {}ms - {}ms",
+                "Retry Delay".cyan(),
+                config.upload.retry.base_delay_ms,
+                config.upload.retry.max_delay_ms
+            );
+            println!("  {}: {}s", "Connect Timeout".cyan(), config.upload.retry.connect_timeout_secs);
+            let timeout = match config.upload.retry.timeout_secs {
+                0 => "none".to_string(),
+                secs => format!("{}s", secs),
+            };
+            println!("  {}: {}", "Request Timeout".cyan(), timeout);
             
             println!("\n{}: {}", "Config File".green(), Config::config_path().display());
             
@@ -0,0 +1,203 @@
+use anyhow::Result;
+use clap::{ArgGroup, Args};
+use std::collections::HashSet;
+
+use crate::cloudinary::admin::{AdminApi, ResourceQuery, DELETE_BATCH_SIZE};
+use crate::config::{Config, DeliveryType, ResourceType, stored_resource_type};
+use crate::utils::{
+    confirm, create_progress_bar, print_info, print_success, print_warning, read_public_ids,
+};
+
+/// Number of public IDs shown in the plan before asking for confirmation
+const PLAN_PREVIEW: usize = 20;
+
+#[derive(Args, Clone, Debug)]
+#[command(group(
+    ArgGroup::new("targets")
+        .required(true)
+        .multiple(true)
+        .args(["public_ids", "prefix", "tag", "from"])
+))]
+pub struct DeleteArgs {
+    /// Public IDs of the assets to delete
+    #[arg(value_name = "PUBLIC_ID")]
+    pub public_ids: Vec<String>,
+
+    /// Delete every asset whose public ID starts with this prefix
+    #[arg(long, value_name = "PREFIX")]
+    pub prefix: Option<String>,
+
+    /// Delete every asset with this tag
+    #[arg(long, value_name = "TAG")]
+    pub tag: Option<String>,
+
+    /// Read public IDs from a file, one per line (- for stdin)
+    #[arg(long, value_name = "FILE")]
+    pub from: Option<String>,
+
+    /// Delivery type of the assets
+    #[arg(
+        long = "type",
+        value_enum,
+        value_name = "TYPE",
+        default_value = "upload"
+    )]
+    pub delivery_type: DeliveryType,
+
+    /// Resource type of the assets
+    #[arg(long, value_name = "TYPE", default_value = "image", value_parser = stored_resource_type())]
+    pub resource_type: ResourceType,
+
+    /// Also purge the deleted assets from the CDN cache
+    #[arg(long)]
+    pub invalidate: bool,
+
+    /// Only print the public IDs that would be deleted
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Delete without asking for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
+pub async fn execute(args: DeleteArgs) -> Result<()> {
+    let config = Config::load_or_env()?;
+
+    // The confirmation prompt reads stdin too, which is already consumed by then
+    if args.from.as_deref() == Some("-") && !args.yes && !args.dry_run {
+        anyhow::bail!("Public IDs read from stdin cannot be confirmed interactively; pass --yes to delete them");
+    }
+
+    let admin = AdminApi::new(config.cloudinary, &config.upload.retry)?;
+
+    // Resolve every selector into explicit public IDs so the plan is exact
+    let mut public_ids = args.public_ids;
+    if let Some(path) = &args.from {
+        public_ids.extend(read_public_ids(path)?);
+    }
+
+    let query = ResourceQuery {
+        resource_type: args.resource_type,
+        delivery_type: args.delivery_type,
+        prefix: None,
+        tag: None,
+        max: None,
+    };
+    let mut queries = Vec::new();
+    if let Some(prefix) = &args.prefix {
+        queries.push(ResourceQuery {
+            prefix: Some(prefix.clone()),
+            ..query.clone()
+        });
+    }
+    if let Some(tag) = &args.tag {
+        queries.push(ResourceQuery {
+            tag: Some(tag.clone()),
+            ..query.clone()
+        });
+    }
+    for query in &queries {
+        let resources = admin.list_resources(query).await?;
+        public_ids.extend(resources.into_iter().map(|resource| resource.public_id));
+    }
+
+    let mut seen = HashSet::new();
+    public_ids.retain(|id| seen.insert(id.clone()));
+
+    if public_ids.is_empty() {
+        print_info("No assets to delete.");
+        return Ok(());
+    }
+
+    if args.dry_run {
+        for public_id in &public_ids {
+            println!("{}", public_id);
+        }
+        print_info(&format!(
+            "Dry run: {} {} asset(s) of type {} would be deleted.",
+            public_ids.len(),
+            args.resource_type.as_str(),
+            args.delivery_type.as_str()
+        ));
+        return Ok(());
+    }
+
+    print_info(&format!(
+        "About to delete {} {} asset(s) of type {}{}:",
+        public_ids.len(),
+        args.resource_type.as_str(),
+        args.delivery_type.as_str(),
+        if args.invalidate {
+            " and invalidate them on the CDN"
+        } else {
+            ""
+        }
+    ));
+    for public_id in public_ids.iter().take(PLAN_PREVIEW) {
+        println!("  {}", public_id);
+    }
+    if public_ids.len() > PLAN_PREVIEW {
+        println!("  ... and {} more", public_ids.len() - PLAN_PREVIEW);
+    }
+
+    if !args.yes && !confirm("Delete these assets?")? {
+        print_info("Delete aborted.");
+        return Ok(());
+    }
+
+    let pb = create_progress_bar(public_ids.len() as u64);
+    let mut deleted = 0;
+    let mut not_found = Vec::new();
+    let mut failures = Vec::new();
+
+    for batch in public_ids.chunks(DELETE_BATCH_SIZE) {
+        let result = admin
+            .delete_resources(
+                args.resource_type,
+                args.delivery_type,
+                batch,
+                args.invalidate,
+            )
+            .await;
+        pb.inc(batch.len() as u64);
+
+        match result {
+            Ok(outcomes) => {
+                for (public_id, outcome) in outcomes {
+                    match outcome.as_str() {
+                        "deleted" => deleted += 1,
+                        _ => not_found.push(public_id),
+                    }
+                }
+            }
+            Err(err) => {
+                pb.println(format!(
+                    "Failed to delete {} asset(s): {:#}",
+                    batch.len(),
+                    err
+                ));
+                failures.push(err);
+            }
+        }
+    }
+
+    pb.finish_with_message("Delete completed");
+
+    print_success(&format!("Deleted {} asset(s).", deleted));
+    if !not_found.is_empty() {
+        print_warning(&format!(
+            "{} asset(s) were not found: {}",
+            not_found.len(),
+            not_found.join(", ")
+        ));
+    }
+
+    if let Some(err) = failures.into_iter().next() {
+        return Err(
+            err.context("Delete incomplete, run the command again to retry the remaining assets")
+        );
+    }
+
+    Ok(())
+}
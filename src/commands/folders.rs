@@ -0,0 +1,118 @@
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::cloudinary::admin::{AdminApi, Folder};
+use crate::config::Config;
+use crate::utils::{print_info, print_success};
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum FoldersCommand {
+    /// List the folders below a path, or the root folders
+    Ls(LsArgs),
+
+    /// Create a folder, including missing parent folders
+    Mkdir {
+        /// Folder path, e.g. "products/shoes"
+        #[arg(value_name = "PATH")]
+        path: String,
+    },
+
+    /// Delete an empty folder
+    Rm {
+        /// Folder path, e.g. "products/shoes"
+        #[arg(value_name = "PATH")]
+        path: String,
+    },
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct LsArgs {
+    /// Folder to list (defaults to the root)
+    #[arg(value_name = "PATH", default_value = "")]
+    pub path: String,
+
+    /// Show all nested folders as a tree
+    #[arg(long)]
+    pub tree: bool,
+}
+
+pub async fn execute(command: FoldersCommand) -> Result<()> {
+    let config = Config::load_or_env()?;
+    let admin = AdminApi::new(config.cloudinary, &config.upload.retry)?;
+
+    match command {
+        FoldersCommand::Ls(args) => {
+            let path = args.path.trim_matches('/');
+            if args.tree {
+                print_tree(&admin, path).await?;
+            } else {
+                let folders = admin.subfolders(path).await?;
+                if folders.is_empty() {
+                    print_info("No folders found.");
+                }
+                for folder in folders {
+                    println!("{}", folder.path);
+                }
+            }
+        }
+        FoldersCommand::Mkdir { path } => {
+            admin.create_folder(&path).await?;
+            print_success(&format!("Created folder: {}", path.trim_matches('/')));
+        }
+        FoldersCommand::Rm { path } => {
+            admin.delete_folder(&path).await?;
+            print_success(&format!("Deleted folder: {}", path.trim_matches('/')));
+        }
+    }
+
+    Ok(())
+}
+
+/// Print every folder below `root` the way tree(1) prints directories
+async fn print_tree(admin: &AdminApi, root: &str) -> Result<()> {
+    // Fetch level by level, then render from the collected children
+    let mut children: BTreeMap<String, Vec<Folder>> = BTreeMap::new();
+    let mut pending = VecDeque::from([root.to_string()]);
+    while let Some(path) = pending.pop_front() {
+        let folders = admin.subfolders(&path).await?;
+        pending.extend(folders.iter().map(|folder| folder.path.clone()));
+        children.insert(path, folders);
+    }
+
+    println!("{}", if root.is_empty() { "." } else { root });
+    let mut count = 0;
+    render_tree(&children, root, "", &mut count);
+    println!(
+        "\n{} {}",
+        count,
+        if count == 1 { "folder" } else { "folders" }
+    );
+
+    Ok(())
+}
+
+fn render_tree(
+    children: &BTreeMap<String, Vec<Folder>>,
+    path: &str,
+    prefix: &str,
+    count: &mut usize,
+) {
+    let Some(folders) = children.get(path) else {
+        return;
+    };
+
+    for (index, folder) in folders.iter().enumerate() {
+        let last = index + 1 == folders.len();
+        println!(
+            "{}{}{}",
+            prefix,
+            if last { "└── " } else { "├── " },
+            folder.name
+        );
+        *count += 1;
+
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        render_tree(children, &folder.path, &child_prefix, count);
+    }
+}
@@ -0,0 +1,139 @@
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+
+use crate::cloudinary::admin::{AdminApi, ResourceDetails};
+use crate::cloudinary::AssetMetadata;
+use crate::config::{Config, DeliveryType, ResourceType, stored_resource_type};
+use crate::utils::{format_size, print_info};
+
+#[derive(Args, Clone, Debug)]
+pub struct InfoArgs {
+    /// Public ID of the asset
+    #[arg(value_name = "PUBLIC_ID")]
+    pub public_id: String,
+
+    /// Delivery type of the asset
+    #[arg(long = "type", value_enum, value_name = "TYPE", default_value = "upload")]
+    pub delivery_type: DeliveryType,
+
+    /// Resource type of the asset
+    #[arg(long, value_name = "TYPE", default_value = "image", value_parser = stored_resource_type())]
+    pub resource_type: ResourceType,
+
+    /// Print the details as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+pub async fn execute(args: InfoArgs) -> Result<()> {
+    let config = Config::load_or_env()?;
+
+    let admin = AdminApi::new(config.cloudinary, &config.upload.retry)?;
+    let details = admin
+        .resource(args.resource_type, args.delivery_type, &args.public_id)
+        .await?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&details)?);
+    } else {
+        print_details(&details);
+    }
+
+    Ok(())
+}
+
+fn print_details(details: &ResourceDetails) {
+    let resource = &details.resource;
+
+    print_info(&resource.public_id);
+    println!("  {}: {}", "Resource Type".cyan(), resource.resource_type);
+    println!("  {}: {}", "Type".cyan(), resource.delivery_type);
+    if let Some(format) = &resource.format {
+        println!("  {}: {}", "Format".cyan(), format);
+    }
+    if let (Some(width), Some(height)) = (resource.width, resource.height) {
+        println!("  {}: {}x{}", "Dimensions".cyan(), width, height);
+    }
+    println!("  {}: {} ({} bytes)", "Size".cyan(), format_size(resource.bytes), resource.bytes);
+    println!("  {}: {}", "Version".cyan(), resource.version);
+    println!("  {}: {}", "Created At".cyan(), resource.created_at);
+    if let Some(folder) = &details.asset_folder {
+        println!("  {}: {}", "Asset Folder".cyan(), folder);
+    }
+    if let Some(access_mode) = &details.access_mode {
+        println!("  {}: {}", "Access Mode".cyan(), access_mode);
+    }
+    if let Some(url) = resource.secure_url.as_ref().or(details.url.as_ref()) {
+        println!("  {}: {}", "URL".cyan(), url);
+    }
+
+    let labels = [
+        ("Tags", resource.tags.clone()),
+        ("Context", details.context_pairs()),
+        ("Metadata", details.metadata_pairs()),
+    ];
+    for (label, values) in labels {
+        if !values.is_empty() {
+            println!("  {}: {}", label.cyan(), values.join(", "));
+        }
+    }
+
+    if !details.versions.is_empty() {
+        print_info(&format!("Versions ({}):", details.versions.len()));
+        for version in &details.versions {
+            let mut line = match version.version {
+                Some(number) => format!("v{}", number),
+                None => version.version_id.clone(),
+            };
+            if let Some(time) = &version.time {
+                line.push_str(&format!("  {}", time));
+            }
+            if let Some(size) = version.size {
+                line.push_str(&format!("  {}", format_size(size)));
+            }
+            if version.restorable == Some(true) {
+                line.push_str("  (restorable)");
+            }
+            println!("  {}", line);
+        }
+    }
+
+    if !details.derived.is_empty() {
+        print_info(&format!("Derived ({}):", details.derived.len()));
+        for derived in &details.derived {
+            println!(
+                "  {}  {}  {}",
+                derived.transformation.cyan(),
+                format_size(derived.bytes),
+                derived.secure_url.as_deref().unwrap_or_default()
+            );
+        }
+    }
+
+    if let Some(image_metadata) = details.image_metadata.as_ref().filter(|m| !m.is_empty()) {
+        print_info("Image Metadata:");
+        for (key, value) in image_metadata {
+            match value.as_str() {
+                Some(text) => println!("  {}: {}", key.cyan(), text),
+                None => println!("  {}: {}", key.cyan(), value),
+            }
+        }
+    }
+
+    if !details.colors.is_empty() {
+        print_info("Colors:");
+        for (color, share) in &details.colors {
+            println!("  {}  {:.1}%", color, share);
+        }
+    }
+
+    if !details.faces.is_empty() {
+        print_info(&format!("Faces ({}):", details.faces.len()));
+        for face in &details.faces {
+            if let [x, y, width, height] = face.as_slice() {
+                println!("  {}x{} at ({}, {})", width, height, x, y);
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use clap::Args;
+use colored::Colorize;
+
+use crate::cloudinary::admin::{AdminApi, Resource, ResourceQuery};
+use crate::config::{Config, DeliveryType, ResourceType, stored_resource_type};
+use crate::utils::{format_size, print_info};
+
+#[derive(Args, Clone, Debug)]
+pub struct ListArgs {
+    /// Only list public IDs starting with this prefix (e.g. "products/")
+    #[arg(long, value_name = "PREFIX")]
+    pub prefix: Option<String>,
+
+    /// Delivery type of the assets
+    #[arg(long = "type", value_enum, value_name = "TYPE", default_value = "upload")]
+    pub delivery_type: DeliveryType,
+
+    /// Resource type of the assets
+    #[arg(long, value_name = "TYPE", default_value = "image", value_parser = stored_resource_type())]
+    pub resource_type: ResourceType,
+
+    /// Only list assets with this tag
+    #[arg(long, value_name = "TAG", conflicts_with_all = ["prefix", "delivery_type"])]
+    pub tag: Option<String>,
+
+    /// Maximum number of assets to list (defaults to all)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max: Option<u32>,
+
+    /// Print the assets as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+pub async fn execute(args: ListArgs) -> Result<()> {
+    let config = Config::load_or_env()?;
+
+    let admin = AdminApi::new(config.cloudinary, &config.upload.retry)?;
+    let query = ResourceQuery {
+        resource_type: args.resource_type,
+        delivery_type: args.delivery_type,
+        prefix: args.prefix,
+        tag: args.tag,
+        max: args.max.map(|max| max as usize),
+    };
+
+    let resources = admin.list_resources(&query).await?;
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&resources)?);
+        return Ok(());
+    }
+
+    if resources.is_empty() {
+        print_info("No assets found.");
+        return Ok(());
+    }
+
+    print_table(&resources);
+    print_info(&format!("{} assets", resources.len()));
+
+    Ok(())
+}
+
+fn print_table(resources: &[Resource]) {
+    let rows: Vec<[String; 5]> = resources
+        .iter()
+        .map(|resource| {
+            let dimensions = match (resource.width, resource.height) {
+                (Some(width), Some(height)) => format!("{}x{}", width, height),
+                _ => "-".to_string(),
+            };
+            [
+                resource.public_id.clone(),
+                resource.format.clone().unwrap_or_else(|| "-".to_string()),
+                format_size(resource.bytes),
+                dimensions,
+                resource.created_at.clone(),
+            ]
+        })
+        .collect();
+
+    let headers = ["PUBLIC ID", "FORMAT", "SIZE", "DIMENSIONS", "CREATED AT"];
+    let mut widths = headers.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header = headers
+        .iter()
+        .zip(widths)
+        .map(|(header, width)| format!("{:<width$}", header, width = width))
+        .collect::<Vec<_>>()
+        .join("  ");
+    println!("{}", header.trim_end().bold());
+
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
+}
@@ -1,3 +1,10 @@
 pub mod config;
+pub mod delete;
+pub mod folders;
+pub mod info;
 pub mod init;
+pub mod list;
+pub mod mv;
+pub mod tag;
 pub mod upload;
+pub mod url;
@@ -0,0 +1,169 @@
+use anyhow::Result;
+use clap::Args;
+use futures::stream::{self, StreamExt};
+
+use crate::cloudinary::admin::{AdminApi, ResourceQuery};
+use crate::config::{Config, DeliveryType, ResourceType, stored_resource_type};
+use crate::utils::{create_progress_bar, print_error, print_info, print_success};
+
+#[derive(Args, Clone, Debug)]
+pub struct MvArgs {
+    /// Current public ID of the asset
+    #[arg(
+        value_name = "FROM",
+        required_unless_present = "prefix",
+        conflicts_with = "prefix"
+    )]
+    pub from_public_id: Option<String>,
+
+    /// New public ID of the asset
+    #[arg(value_name = "TO", required_unless_present = "prefix")]
+    pub to_public_id: Option<String>,
+
+    /// Move every asset whose public ID starts with this prefix
+    #[arg(long, value_name = "PREFIX", requires = "to_prefix")]
+    pub prefix: Option<String>,
+
+    /// Prefix that replaces --prefix in the moved public IDs
+    #[arg(long = "to", value_name = "PREFIX", requires = "prefix")]
+    pub to_prefix: Option<String>,
+
+    /// Delivery type of the assets
+    #[arg(
+        long = "type",
+        value_enum,
+        value_name = "TYPE",
+        default_value = "upload"
+    )]
+    pub delivery_type: DeliveryType,
+
+    /// Resource type of the assets
+    #[arg(long, value_name = "TYPE", default_value = "image", value_parser = stored_resource_type())]
+    pub resource_type: ResourceType,
+
+    /// Replace an existing asset with the target public ID
+    #[arg(long)]
+    pub overwrite: bool,
+
+    /// Purge the old URLs from the CDN cache
+    #[arg(long)]
+    pub invalidate: bool,
+}
+
+pub async fn execute(args: MvArgs) -> Result<()> {
+    let config = Config::load_or_env()?;
+
+    let admin = AdminApi::new(config.cloudinary, &config.upload.retry)?;
+
+    match (
+        &args.prefix,
+        &args.to_prefix,
+        &args.from_public_id,
+        &args.to_public_id,
+    ) {
+        (Some(prefix), Some(to_prefix), _, _) => {
+            move_prefix(&admin, &args, prefix, to_prefix, config.upload.concurrency).await
+        }
+        (_, _, Some(from), Some(to)) => move_one(&admin, &args, from, to).await,
+        _ => anyhow::bail!("Give FROM and TO public IDs, or --prefix and --to"),
+    }
+}
+
+async fn move_one(admin: &AdminApi, args: &MvArgs, from: &str, to: &str) -> Result<()> {
+    let resource = admin
+        .rename(
+            args.resource_type,
+            args.delivery_type,
+            from,
+            to,
+            args.overwrite,
+            args.invalidate,
+        )
+        .await?;
+    print_success(&format!("Moved {} -> {}", from, resource.public_id));
+    Ok(())
+}
+
+/// Rename every asset under `prefix`, a bounded number at a time
+async fn move_prefix(
+    admin: &AdminApi,
+    args: &MvArgs,
+    prefix: &str,
+    to_prefix: &str,
+    concurrency: usize,
+) -> Result<()> {
+    let query = ResourceQuery {
+        resource_type: args.resource_type,
+        delivery_type: args.delivery_type,
+        prefix: Some(prefix.to_string()),
+        tag: None,
+        max: None,
+    };
+    let moves: Vec<(String, String)> = admin
+        .list_resources(&query)
+        .await?
+        .into_iter()
+        .filter_map(|resource| {
+            let rest = resource.public_id.strip_prefix(prefix)?.to_string();
+            Some((resource.public_id, format!("{}{}", to_prefix, rest)))
+        })
+        .collect();
+
+    if moves.is_empty() {
+        print_info(&format!("No assets found under {}", prefix));
+        return Ok(());
+    }
+
+    print_info(&format!(
+        "Moving {} assets from {} to {}...",
+        moves.len(),
+        prefix,
+        to_prefix
+    ));
+
+    let pb = create_progress_bar(moves.len() as u64);
+    let admin = admin.clone().with_progress(pb.clone());
+    let failures: Vec<(String, anyhow::Error)> = stream::iter(moves.iter())
+        .map(|(from, to)| {
+            let admin = &admin;
+            let pb = &pb;
+            async move {
+                let result = admin
+                    .rename(
+                        args.resource_type,
+                        args.delivery_type,
+                        from,
+                        to,
+                        args.overwrite,
+                        args.invalidate,
+                    )
+                    .await;
+                pb.inc(1);
+                result.err().map(|err| (from.clone(), err))
+            }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .filter_map(|failure| async move { failure })
+        .collect()
+        .await;
+
+    pb.finish_with_message("Move completed");
+
+    print_success(&format!(
+        "Moved {} of {} assets.",
+        moves.len() - failures.len(),
+        moves.len()
+    ));
+
+    if !failures.is_empty() {
+        print_error(&format!("{} assets could not be moved:", failures.len()));
+        for (public_id, err) in &failures {
+            println!("  {}: {:#}", public_id, err);
+        }
+    }
+    if let Some((_, err)) = failures.into_iter().next() {
+        return Err(err.context("Move incomplete"));
+    }
+
+    Ok(())
+}
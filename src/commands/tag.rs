@@ -0,0 +1,126 @@
+use anyhow::Result;
+use clap::{Args, Subcommand};
+
+use crate::cloudinary::admin::{AdminApi, TagOperation, TAG_BATCH_SIZE};
+use crate::config::{Config, DeliveryType, ResourceType, stored_resource_type};
+use crate::utils::{create_progress_bar, print_info, print_success, read_public_ids};
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum TagCommand {
+    /// Add a tag to assets
+    Add(TagArgs),
+
+    /// Remove a tag from assets
+    Remove(TagArgs),
+
+    /// Replace all tags of assets with a single tag
+    Replace(TagArgs),
+
+    /// Remove every tag from assets
+    Clear(TargetArgs),
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct TagArgs {
+    /// Tag to apply
+    #[arg(value_name = "TAG")]
+    pub tag: String,
+
+    #[command(flatten)]
+    pub targets: TargetArgs,
+}
+
+/// The assets a tag command applies to
+#[derive(Args, Clone, Debug)]
+pub struct TargetArgs {
+    /// Public IDs of the assets (read from stdin when none are given)
+    #[arg(value_name = "PUBLIC_ID")]
+    pub public_ids: Vec<String>,
+
+    /// Read public IDs from a file, one per line (- for stdin)
+    #[arg(long, value_name = "FILE")]
+    pub from: Option<String>,
+
+    /// Delivery type of the assets
+    #[arg(
+        long = "type",
+        value_enum,
+        value_name = "TYPE",
+        default_value = "upload"
+    )]
+    pub delivery_type: DeliveryType,
+
+    /// Resource type of the assets
+    #[arg(long, value_name = "TYPE", default_value = "image", value_parser = stored_resource_type())]
+    pub resource_type: ResourceType,
+}
+
+pub async fn execute(command: TagCommand) -> Result<()> {
+    let (operation, tag, targets) = match command {
+        TagCommand::Add(args) => (TagOperation::Add, Some(args.tag), args.targets),
+        TagCommand::Remove(args) => (TagOperation::Remove, Some(args.tag), args.targets),
+        TagCommand::Replace(args) => (TagOperation::Replace, Some(args.tag), args.targets),
+        TagCommand::Clear(targets) => (TagOperation::RemoveAll, None, targets),
+    };
+
+    let config = Config::load_or_env()?;
+
+    let mut public_ids = targets.public_ids;
+    match &targets.from {
+        Some(path) => public_ids.extend(read_public_ids(path)?),
+        None if public_ids.is_empty() => public_ids = read_public_ids("-")?,
+        None => {}
+    }
+
+    if public_ids.is_empty() {
+        print_info("No public IDs given.");
+        return Ok(());
+    }
+
+    let admin = AdminApi::new(config.cloudinary, &config.upload.retry)?;
+
+    let pb = create_progress_bar(public_ids.len() as u64);
+    let admin = admin.with_progress(pb.clone());
+    let mut updated = 0;
+
+    for batch in public_ids.chunks(TAG_BATCH_SIZE) {
+        let result = admin
+            .tags(
+                operation,
+                tag.as_deref(),
+                targets.resource_type,
+                targets.delivery_type,
+                batch,
+            )
+            .await;
+        pb.inc(batch.len() as u64);
+
+        match result {
+            Ok(ids) => updated += ids.len(),
+            Err(err) => {
+                pb.abandon();
+                return Err(err.context(format!(
+                    "Tagging stopped after {} of {} assets",
+                    updated,
+                    public_ids.len()
+                )));
+            }
+        }
+    }
+
+    pb.finish_with_message("Tagging completed");
+
+    let summary = match (operation, &tag) {
+        (TagOperation::Add, Some(tag)) => format!("Added '{}' to {} asset(s).", tag, updated),
+        (TagOperation::Remove, Some(tag)) => {
+            format!("Removed '{}' from {} asset(s).", tag, updated)
+        }
+        (TagOperation::Replace, Some(tag)) => {
+            format!("Replaced the tags of {} asset(s) with '{}'.", updated, tag)
+        }
+        _ => format!("Cleared the tags of {} asset(s).", updated),
+    };
+    print_success(&summary);
+
+    Ok(())
+}
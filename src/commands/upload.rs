@@ -1,85 +1,385 @@
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use clap::Args;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
 use futures::future::try_join_all;
 
-use crate::cloudinary::CloudinaryUploader;
-use crate::config::Config;
-use crate::utils::{create_progress_bar, print_error, print_info, print_success, save_urls_to_file};
-use crate::utils::fzf::{get_files_in_directory, select_files};
-
-pub async fn execute(
-    path: Option<String>,
-    folder: Option<String>,
-    transform: Option<String>,
-    output: Option<String>,
-) -> Result<()> {
+use crate::cloudinary::journal::{file_mtime, UploadJournal};
+use crate::cloudinary::scheduler::UploadScheduler;
+use crate::cloudinary::public_id::PublicIdTemplate;
+use crate::cloudinary::source::is_remote_url;
+use crate::cloudinary::admin::AdminApi;
+use crate::cloudinary::error::exit_code;
+use crate::cloudinary::{AssetMetadata, CloudinaryUploader, Transformation, UploadOptions, UploadSource};
+use crate::config::{Config, DeliveryType, FolderMode, ResourceType};
+use crate::utils::{
+    create_progress_bar, print_error, print_info, print_success, print_warning, save_urls_to_file,
+};
+use crate::utils::fzf::{get_files_in_directory, select_directory, select_files};
+
+#[derive(Args, Clone, Debug)]
+pub struct UploadArgs {
+    /// Path to a file or directory, an http(s) URL, a data URI, or - for stdin (optional, defaults to interactive selection)
+    #[arg(value_name = "PATH")]
+    pub path: Option<String>,
+    
+    /// Specify Cloudinary destination folder
+    #[arg(short, long, value_name = "FOLDER")]
+    pub folder: Option<String>,
+    
+    /// Apply transformation (e.g., webp, avif or w_800,c_fill,q_auto/f_webp)
+    #[arg(short, long, value_name = "TRANSFORMATION", value_parser = parse_incoming_transformation)]
+    pub transform: Option<Transformation>,
+    
+    /// Save URLs to a specified file
+    #[arg(short, long, value_name = "FILE")]
+    pub output: Option<String>,
+
+    /// List interrupted uploads and finish them with the options they were started with
+    #[arg(long, conflicts_with_all = [
+        "path", "urls_from", "filename", "mime", "folder", "transform", "preset", "unsigned",
+        "public_id_template", "use_filename", "unique_filename", "overwrite",
+        "preserve_structure", "folder_mode", "tags", "context", "metadata", "eager",
+        "eager_async", "notification_url", "resource_type", "delivery_type", "check_folder",
+    ])]
+    pub resume: bool,
+
+    /// Maximum number of files uploaded at the same time
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub concurrency: Option<u16>,
+
+    /// Upload the remote URLs listed in a file, one per line
+    #[arg(long, value_name = "FILE")]
+    pub urls_from: Option<String>,
+
+    /// File name sent for data read from stdin or a data URI
+    #[arg(long, value_name = "NAME")]
+    pub filename: Option<String>,
+
+    /// MIME type of data read from stdin or a data URI (e.g., image/png)
+    #[arg(long, value_name = "TYPE")]
+    pub mime: Option<String>,
+
+    /// Upload preset to apply
+    #[arg(long, value_name = "NAME")]
+    pub preset: Option<String>,
+
+    /// Upload without a signature using an unsigned upload preset (no API secret needed)
+    #[arg(long)]
+    pub unsigned: bool,
+
+    /// Public ID pattern, e.g. "blog/{date:%Y/%m}/{stem}-{sha1:8}"
+    /// (placeholders: {stem}, {ext}, {relpath}, {parent}, {sha1[:N]}, {date:FORMAT})
+    #[arg(long, value_name = "TEMPLATE")]
+    pub public_id_template: Option<String>,
+
+    /// Use the original file name as the public ID
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub use_filename: Option<bool>,
+
+    /// Append a random suffix to the public ID to keep it unique
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub unique_filename: Option<bool>,
+
+    /// Overwrite an existing asset with the same public ID
+    #[arg(long, value_name = "BOOL", num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub overwrite: Option<bool>,
+
+    /// Mirror the local directory structure into sub-folders of the target folder
+    #[arg(long)]
+    pub preserve_structure: bool,
+
+    /// Folder mode of the account (defaults to the folder_mode config key)
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub folder_mode: Option<FolderMode>,
+
+    /// Tag to add to the uploaded assets (repeatable)
+    #[arg(long = "tag", value_name = "TAG", value_parser = parse_tag)]
+    pub tags: Vec<String>,
+
+    /// Contextual metadata to attach, as key=value (repeatable)
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    pub context: Vec<(String, String)>,
+
+    /// Structured metadata to attach, as external_id=value (repeatable)
+    #[arg(long, value_name = "EXTERNAL_ID=VALUE", value_parser = parse_key_value)]
+    pub metadata: Vec<(String, String)>,
+
+    /// Also return a delivery URL with this transformation applied (repeatable)
+    #[arg(long = "derive", value_name = "TRANSFORMATION", value_parser = parse_transformation)]
+    pub derive: Vec<Transformation>,
+
+    /// Generate a derivative with this transformation at upload time (repeatable)
+    #[arg(long = "eager", value_name = "TRANSFORMATION", value_parser = parse_transformation)]
+    pub eager: Vec<Transformation>,
+
+    /// Generate eager derivatives in the background
+    #[arg(long, requires = "eager")]
+    pub eager_async: bool,
+
+    /// URL notified when asynchronous processing has finished
+    #[arg(long, value_name = "URL", value_parser = parse_notification_url)]
+    pub notification_url: Option<String>,
+
+    /// Upload endpoint to use instead of detecting it from the content and extension
+    #[arg(long, value_enum, value_name = "TYPE")]
+    pub resource_type: Option<ResourceType>,
+
+    /// Delivery type controlling who can access the uploaded assets
+    #[arg(long = "type", value_enum, value_name = "TYPE")]
+    pub delivery_type: Option<DeliveryType>,
+
+    /// Fail unless the destination folder already exists, to catch typos
+    #[arg(long)]
+    pub check_folder: bool,
+}
+
+pub async fn execute(args: UploadArgs) -> Result<()> {
+    let UploadArgs {
+        path,
+        folder,
+        transform,
+        output,
+        resume,
+        concurrency,
+        urls_from,
+        filename,
+        mime,
+        preset,
+        unsigned,
+        public_id_template,
+        use_filename,
+        unique_filename,
+        overwrite,
+        preserve_structure,
+        folder_mode,
+        tags,
+        context,
+        metadata,
+        derive,
+        eager,
+        eager_async,
+        notification_url,
+        resource_type,
+        delivery_type,
+        check_folder,
+    } = args;
+
+    let public_id_template = public_id_template
+        .as_deref()
+        .map(PublicIdTemplate::parse)
+        .transpose()?;
+
     // Load configuration
-    let config = match Config::load() {
-        Ok(config) => config,
-        Err(_) => {
-            if let Some(env_config) = Config::from_env() {
-                env_config
-            } else {
-                print_error("No configuration found. Please run 'cloudy init' first or set CLOUDINARY_URL environment variable.");
-                return Ok(());
-            }
-        }
-    };
+    let config = Config::load_or_env()?;
 
     // Create Cloudinary uploader
-    let uploader = CloudinaryUploader::new(config.cloudinary.clone());
+    let uploader = CloudinaryUploader::new(config.cloudinary.clone(), config.upload.clone());
 
-    // Determine files to upload
-    let files = if let Some(path_str) = path {
-        let path = PathBuf::from(path_str);
-        
-        if path.is_dir() {
-            get_files_in_directory(&path).context("Failed to get files in directory")?
-        } else if path.is_file() {
-            vec![path]
-        } else {
-            print_error(&format!("Path not found: {}", path.display()));
+    let options = UploadOptions {
+        folder,
+        transformation: transform,
+        upload_preset: preset,
+        unsigned,
+        use_filename,
+        unique_filename,
+        overwrite,
+        folder_mode: folder_mode.unwrap_or(config.cloudinary.folder_mode),
+        tags,
+        context,
+        metadata,
+        eager,
+        eager_async,
+        notification_url,
+        resource_type,
+        delivery_type,
+        ..Default::default()
+    };
+
+    // Determine what to upload, each with the options it should be uploaded with
+    let jobs: Vec<(UploadSource, UploadOptions)> = if resume {
+        let journals = interrupted_uploads()?;
+        if journals.is_empty() {
+            print_info("No interrupted uploads to resume.");
             return Ok(());
         }
+
+        print_info("Interrupted uploads:");
+        for journal in &journals {
+            println!(
+                "  {} ({} of {} bytes uploaded)",
+                journal.file_path.display(),
+                journal.next_offset().min(journal.file_size),
+                journal.file_size
+            );
+        }
+
+        journals
+            .into_iter()
+            .map(|journal| (UploadSource::File(journal.file_path), journal.options))
+            .collect()
     } else {
-        // Interactive selection using fzf
-        print_info("Select files to upload (use Tab to select multiple files):");
-        select_files().context("Failed to select files")?
+        // Each source is paired with the directory its {relpath} is relative to
+        let mut sources: Vec<(UploadSource, Option<PathBuf>)> = Vec::new();
+
+        if let Some(urls_path) = &urls_from {
+            sources.extend(read_url_list(urls_path)?.into_iter().map(|source| (source, None)));
+        }
+
+        if path.as_deref() == Some("-") {
+            let mut data = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut data)
+                .context("Failed to read from stdin")?;
+            sources.push((UploadSource::from_stdin(data, filename.as_deref(), mime.as_deref())?, None));
+        } else if let Some(path_str) = path {
+            match UploadSource::parse(&path_str, filename.as_deref(), mime.as_deref())? {
+                UploadSource::File(path) => {
+                    if path.is_dir() {
+                        let files = get_files_in_directory(&path).context("Failed to get files in directory")?;
+                        sources.extend(files.into_iter().map(|file| (UploadSource::File(file), Some(path.clone()))));
+                    } else if path.is_file() {
+                        let root = path.parent().map(PathBuf::from);
+                        sources.push((UploadSource::File(path), root));
+                    } else {
+                        anyhow::bail!("Path not found: {}", path.display());
+                    }
+                }
+                other => sources.push((other, None)),
+            }
+        } else if preserve_structure && urls_from.is_none() {
+            // Mirroring a structure needs a root, so pick a whole directory
+            print_info("Select a directory to upload:");
+            let dir = select_directory().context("Failed to select directory")?;
+            let files = get_files_in_directory(&dir).context("Failed to get files in directory")?;
+            sources.extend(files.into_iter().map(|file| (UploadSource::File(file), Some(dir.clone()))));
+        } else if urls_from.is_none() {
+            // Interactive selection using fzf
+            print_info("Select files to upload (use Tab to select multiple files):");
+            let files = select_files().context("Failed to select files")?;
+            sources.extend(files.into_iter().map(|file| (UploadSource::File(file), None)));
+        }
+
+        let mut jobs = Vec::new();
+        for (source, root) in sources {
+            let mut options = options.clone();
+            if let Some(template) = &public_id_template {
+                options.public_id = Some(template.render(&source, root.as_deref())?);
+            }
+            if preserve_structure {
+                if let (UploadSource::File(file), Some(root)) = (&source, &root) {
+                    mirror_structure(&mut options, file, root, &config.cloudinary.default_folder);
+                }
+            }
+            jobs.push((source, options));
+        }
+        jobs
     };
 
-    if files.is_empty() {
-        print_error("No files selected for upload.");
+    if jobs.is_empty() {
+        print_error("Nothing selected for upload.");
         return Ok(());
     }
 
-    print_info(&format!("Uploading {} files to Cloudinary...", files.len()));
+    // Only the base target has to exist; sub-folders created by
+    // --preserve-structure are new by design
+    if check_folder {
+        let folder = options
+            .folder
+            .as_deref()
+            .unwrap_or(&config.cloudinary.default_folder)
+            .trim_matches('/');
+        if !folder.is_empty() {
+            let admin = AdminApi::new(config.cloudinary.clone(), &config.upload.retry)?;
+            if !admin.folder_exists(folder).await? {
+                anyhow::bail!(
+                    "Folder '{}' does not exist; create it with 'cloudy folders mkdir {}'",
+                    folder,
+                    folder
+                );
+            }
+        }
+    }
+
+    print_info(&format!("Uploading {} files to Cloudinary...", jobs.len()));
     
     // Create progress bar
-    let pb = create_progress_bar(files.len() as u64);
-    
-    // Upload files in parallel
+    let pb = create_progress_bar(jobs.len() as u64);
+    let uploader = uploader.with_progress(pb.clone());
+
+    // Upload files in parallel, bounded by the configured concurrency
+    let scheduler = UploadScheduler::new(
+        concurrency.map(usize::from).unwrap_or(config.upload.concurrency),
+        config.upload.video_concurrency,
+    );
     let mut upload_tasks = Vec::new();
     
-    for file in files {
+    for (source, options) in jobs {
         let uploader = uploader.clone();
+        let scheduler = scheduler.clone();
         let pb = pb.clone();
-        let folder_clone = folder.clone();
-        let transform_clone = transform.clone();
+        let derive = derive.clone();
         
         let task = tokio::spawn(async move {
-            let folder_ref = folder_clone.as_deref();
-            let transform_ref = transform_clone.as_deref();
-            
-            let result = uploader.upload_file(&file, folder_ref, transform_ref).await;
+            let _slot = scheduler
+                .acquire(uploader.source_resource_type(&source, &options))
+                .await?;
+            let result = uploader.upload(&source, &options).await;
             pb.inc(1);
             
             match result {
                 Ok(response) => {
-                    pb.println(format!("Uploaded: {} -> {}", file.display(), response.secure_url));
-                    Ok(response.secure_url)
+                    pb.println(format!("Uploaded: {} -> {}", source, response.secure_url));
+
+                    // Derived URLs are delivery-time transformations of the stored
+                    // master, so no further upload or conversion is needed
+                    let mut urls = vec![response.secure_url.clone()];
+                    for transformation in &derive {
+                        let url = uploader.get_url(
+                            &response.public_id,
+                            &response.resource_type,
+                            response.delivery_type,
+                            Some(transformation),
+                        );
+                        match url {
+                            Ok(url) => {
+                                pb.println(format!("  {} -> {}", transformation, url));
+                                urls.push(url);
+                            }
+                            Err(err) => pb.println(format!("  {} -> {:#}", transformation, err)),
+                        }
+                    }
+
+                    for eager in response.eager.iter().flatten() {
+                        match &eager.secure_url {
+                            Some(url) => {
+                                pb.println(format!("  {} (eager) -> {}", eager.transformation, url));
+                                urls.push(url.clone());
+                            }
+                            None => pb.println(format!(
+                                "  {} (eager) -> {}",
+                                eager.transformation,
+                                eager.status.as_deref().unwrap_or("pending")
+                            )),
+                        }
+                    }
+
+                    let tags = response.tags.clone().unwrap_or_default();
+                    let labels = [
+                        ("Tags", tags),
+                        ("Context", response.context_pairs()),
+                        ("Metadata", response.metadata_pairs()),
+                    ];
+                    for (label, values) in labels {
+                        if !values.is_empty() {
+                            pb.println(format!("  {}: {}", label, values.join(", ")));
+                        }
+                    }
+
+                    Ok(urls)
                 }
                 Err(err) => {
-                    pb.println(format!("Failed to upload {}: {}", file.display(), err));
+                    pb.println(format!("Failed to upload {}: {:#}", source, err));
                     Err(err)
                 }
             }
@@ -89,20 +389,27 @@ pub async fn execute(
     }
     
     // Wait for all uploads to complete
-    let results: Vec<Result<String, _>> = try_join_all(upload_tasks)
+    let results: Vec<Result<Vec<String>, _>> = try_join_all(upload_tasks)
         .await?
         .into_iter()
         .collect();
     
     pb.finish_with_message("Upload completed");
     
-    // Collect successful uploads
-    let successful_urls: Vec<String> = results
-        .into_iter()
-        .filter_map(|r| r.ok())
-        .collect();
+    // Collect successful uploads, each with its original and derived URLs
+    let total = results.len();
+    let mut successful_uploads: Vec<Vec<String>> = Vec::new();
+    let mut failures = Vec::new();
+    for result in results {
+        match result {
+            Ok(urls) => successful_uploads.push(urls),
+            Err(err) => failures.push(err),
+        }
+    }
     
-    print_success(&format!("Successfully uploaded {} files.", successful_urls.len()));
+    print_success(&format!("Successfully uploaded {} files.", successful_uploads.len()));
+
+    let successful_urls: Vec<String> = successful_uploads.into_iter().flatten().collect();
     
     // Save URLs to file if requested
     if let Some(output_path) = output {
@@ -112,6 +419,318 @@ pub async fn execute(
         
         print_success(&format!("URLs saved to: {}", output_path.display()));
     }
+
+    // Exit with the failure's own code when every failed upload failed the same way
+    if let Some(first) = failures.first() {
+        let summary = format!("{} of {} uploads failed", failures.len(), total);
+        let code = exit_code(first);
+        if failures.iter().all(|err| exit_code(err) == code) {
+            let first = failures.swap_remove(0);
+            return Err(first.context(summary));
+        }
+        anyhow::bail!(summary);
+    }
     
     Ok(())
 }
+
+/// Parse and validate a transformation argument
+pub fn parse_transformation(value: &str) -> Result<Transformation, String> {
+    value.parse().map_err(|err| format!("{:#}", err))
+}
+
+/// Parse a transformation applied to the stored asset at upload time
+fn parse_incoming_transformation(value: &str) -> Result<Transformation, String> {
+    let transformation = parse_transformation(value)?;
+    transformation.check_incoming().map_err(|err| err.to_string())?;
+    Ok(transformation)
+}
+
+/// Accept only http(s) URLs for notifications
+fn parse_notification_url(value: &str) -> Result<String, String> {
+    if is_remote_url(value) {
+        Ok(value.to_string())
+    } else {
+        Err(format!("expected an http(s) URL, got '{}'", value))
+    }
+}
+
+/// Accept a single tag; Cloudinary would split one containing commas into several
+fn parse_tag(value: &str) -> Result<String, String> {
+    if value.contains(',') {
+        Err(format!("tags cannot contain ',', use --tag once per tag: '{}'", value))
+    } else if value.trim().is_empty() {
+        Err("tag cannot be empty".to_string())
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// Parse a `key=value` argument, splitting at the first `=`
+fn parse_key_value(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{}'", value)),
+    }
+}
+
+/// Place `file` in the remote sub-folder matching its directory below `root`.
+///
+/// `assets/blog/2024/x.png` uploaded from `assets` with folder `site` ends up as
+/// `site/blog/2024/x`. Fixed folder mode gets there through the `folder`
+/// parameter; dynamic folder mode sets the asset folder and prefixes the public
+/// ID with the same path, since there the folder is not part of the public ID.
+fn mirror_structure(options: &mut UploadOptions, file: &Path, root: &Path, default_folder: &str) {
+    let relative_dir = file
+        .parent()
+        .and_then(|parent| parent.strip_prefix(root).ok())
+        .map(|dir| {
+            dir.components()
+                .filter_map(|component| match component {
+                    Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let base_folder = options.folder.as_deref().unwrap_or(default_folder);
+    let folder = base_folder
+        .split('/')
+        .map(str::to_string)
+        .chain(relative_dir)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let public_id = match options.public_id.take() {
+        Some(public_id) => public_id,
+        None if options.use_filename == Some(true) => String::new(),
+        None => stem,
+    };
+
+    options.public_id = match options.folder_mode {
+        FolderMode::Fixed => Some(public_id),
+        FolderMode::Dynamic if folder.is_empty() => Some(public_id),
+        FolderMode::Dynamic => Some(format!("{}/{}", folder, public_id)),
+    }
+    .filter(|id| !id.is_empty() && !id.ends_with('/'));
+    options.folder = Some(folder);
+}
+
+/// Read remote URLs to upload from a file, one per line.
+///
+/// Blank lines and lines starting with `#` are skipped.
+fn read_url_list(path: &str) -> Result<Vec<UploadSource>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read URL list: {}", path))?;
+
+    let mut sources = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if !is_remote_url(line) {
+            anyhow::bail!("{}:{}: not an http(s) URL: {}", path, index + 1, line);
+        }
+        sources.push(UploadSource::Url(line.to_string()));
+    }
+
+    Ok(sources)
+}
+
+/// Load the upload journals whose files are still unchanged on disk.
+///
+/// Journals for files that were deleted or modified since the upload started
+/// can never be resumed, so they are discarded.
+fn interrupted_uploads() -> Result<Vec<UploadJournal>> {
+    let mut journals = Vec::new();
+
+    for journal in UploadJournal::list()? {
+        let unchanged = std::fs::metadata(&journal.file_path)
+            .map(|metadata| {
+                journal.file_size == metadata.len()
+                    && journal.mtime == file_mtime(&metadata)
+            })
+            .unwrap_or(false);
+
+        if unchanged {
+            journals.push(journal);
+        } else {
+            print_warning(&format!(
+                "Discarding interrupted upload of {}: file was changed or removed",
+                journal.file_path.display()
+            ));
+            journal.remove()?;
+        }
+    }
+
+    Ok(journals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_single_tags() {
+        assert_eq!(parse_tag("summer 2024").unwrap(), "summer 2024");
+    }
+
+    #[test]
+    fn rejects_tags_with_commas() {
+        assert!(parse_tag("a,b").unwrap_err().contains("use --tag once per tag"));
+        assert!(parse_tag(",").is_err());
+    }
+
+    #[test]
+    fn rejects_empty_tags() {
+        assert!(parse_tag("").is_err());
+        assert!(parse_tag("  ").is_err());
+    }
+
+    #[test]
+    fn parses_key_values() {
+        assert_eq!(parse_key_value(" alt =a=b").unwrap(), ("alt".to_string(), "a=b".to_string()));
+        assert!(parse_key_value("=value").is_err());
+        assert!(parse_key_value("novalue").is_err());
+    }
+
+    #[derive(clap::Parser)]
+    struct Cli {
+        #[command(flatten)]
+        args: UploadArgs,
+    }
+
+    fn parse_args(args: &[&str]) -> UploadArgs {
+        use clap::Parser;
+        Cli::try_parse_from(std::iter::once("upload").chain(args.iter().copied()))
+            .unwrap()
+            .args
+    }
+
+    #[test]
+    fn boolean_flags_leave_the_path_alone() {
+        let args = parse_args(&["--overwrite", "photo.jpg"]);
+        assert_eq!(args.overwrite, Some(true));
+        assert_eq!(args.path.as_deref(), Some("photo.jpg"));
+
+        let args = parse_args(&["--use-filename", "./x.png", "--unique-filename"]);
+        assert_eq!(args.use_filename, Some(true));
+        assert_eq!(args.unique_filename, Some(true));
+        assert_eq!(args.path.as_deref(), Some("./x.png"));
+    }
+
+    #[test]
+    fn boolean_flags_take_explicit_values() {
+        let args = parse_args(&["photo.jpg", "--overwrite=false", "--unique-filename=true"]);
+        assert_eq!(args.overwrite, Some(false));
+        assert_eq!(args.unique_filename, Some(true));
+        assert_eq!(args.use_filename, None);
+    }
+
+    fn mirrored(folder: Option<&str>, mode: FolderMode, file: &str, default_folder: &str) -> UploadOptions {
+        let mut options = UploadOptions {
+            folder: folder.map(str::to_string),
+            folder_mode: mode,
+            ..Default::default()
+        };
+        mirror_structure(&mut options, Path::new(file), Path::new("assets"), default_folder);
+        options
+    }
+
+    #[test]
+    fn mirrors_sub_folders_in_fixed_mode() {
+        let options = mirrored(Some("site"), FolderMode::Fixed, "assets/blog/2024/x.png", "");
+        assert_eq!(options.folder.as_deref(), Some("site/blog/2024"));
+        assert_eq!(options.public_id.as_deref(), Some("x"));
+    }
+
+    #[test]
+    fn mirrors_sub_folders_in_dynamic_mode() {
+        let options = mirrored(Some("site"), FolderMode::Dynamic, "assets/blog/2024/x.png", "");
+        assert_eq!(options.folder.as_deref(), Some("site/blog/2024"));
+        assert_eq!(options.public_id.as_deref(), Some("site/blog/2024/x"));
+    }
+
+    #[test]
+    fn keeps_files_at_the_root_in_the_base_folder() {
+        let options = mirrored(Some("site/"), FolderMode::Dynamic, "assets/x.png", "");
+        assert_eq!(options.folder.as_deref(), Some("site"));
+        assert_eq!(options.public_id.as_deref(), Some("site/x"));
+    }
+
+    #[test]
+    fn falls_back_to_the_default_folder() {
+        let options = mirrored(None, FolderMode::Fixed, "assets/blog/x.png", "uploads");
+        assert_eq!(options.folder.as_deref(), Some("uploads/blog"));
+    }
+
+    #[test]
+    fn mirrors_without_a_base_folder() {
+        let options = mirrored(None, FolderMode::Fixed, "assets/blog/2024/x.png", "");
+        assert_eq!(options.folder.as_deref(), Some("blog/2024"));
+        assert_eq!(options.public_id.as_deref(), Some("x"));
+
+        let options = mirrored(Some(""), FolderMode::Dynamic, "assets/blog/2024/x.png", "");
+        assert_eq!(options.folder.as_deref(), Some("blog/2024"));
+        assert_eq!(options.public_id.as_deref(), Some("blog/2024/x"));
+    }
+
+    #[test]
+    fn leaves_the_public_id_to_cloudinary_with_use_filename() {
+        for mode in [FolderMode::Fixed, FolderMode::Dynamic] {
+            let mut options = UploadOptions {
+                folder: Some("site".to_string()),
+                folder_mode: mode,
+                use_filename: Some(true),
+                ..Default::default()
+            };
+            mirror_structure(&mut options, Path::new("assets/blog/x.png"), Path::new("assets"), "");
+            assert_eq!(options.folder.as_deref(), Some("site/blog"));
+            assert_eq!(options.public_id, None);
+        }
+    }
+
+    #[test]
+    fn places_explicit_public_ids_in_the_mirrored_folder() {
+        let mut options = UploadOptions {
+            folder: Some("site".to_string()),
+            folder_mode: FolderMode::Fixed,
+            public_id: Some("hero".to_string()),
+            ..Default::default()
+        };
+        mirror_structure(&mut options, Path::new("assets/blog/x.png"), Path::new("assets"), "");
+        assert_eq!(options.folder.as_deref(), Some("site/blog"));
+        assert_eq!(options.public_id.as_deref(), Some("hero"));
+
+        options.public_id = Some("hero".to_string());
+        options.folder = Some("site".to_string());
+        options.folder_mode = FolderMode::Dynamic;
+        mirror_structure(&mut options, Path::new("assets/blog/x.png"), Path::new("assets"), "");
+        assert_eq!(options.public_id.as_deref(), Some("site/blog/hero"));
+    }
+
+    #[test]
+    fn resume_rejects_a_path_and_upload_options() {
+        use clap::Parser;
+        for args in [
+            vec!["--resume", "some/file"],
+            vec!["--resume", "--folder", "x"],
+            vec!["--resume", "--tag", "a"],
+            vec!["--resume", "--overwrite"],
+        ] {
+            let parsed = Cli::try_parse_from(std::iter::once("upload").chain(args.iter().copied()));
+            assert!(parsed.is_err(), "{:?}", args);
+        }
+
+        let args = parse_args(&["--resume", "--concurrency", "2", "--output", "urls.txt"]);
+        assert!(args.resume);
+    }
+}
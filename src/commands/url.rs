@@ -0,0 +1,71 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::cloudinary::delivery::{AuthToken, DeliveryUrl};
+use crate::cloudinary::Transformation;
+use crate::commands::upload::parse_transformation;
+use crate::config::{Config, DeliveryType, ResourceType, stored_resource_type};
+
+#[derive(Args, Clone, Debug)]
+pub struct UrlArgs {
+    /// Public ID of the asset
+    #[arg(value_name = "PUBLIC_ID")]
+    pub public_id: String,
+
+    /// Resource type of the asset
+    #[arg(long, value_name = "TYPE", default_value = "image", value_parser = stored_resource_type())]
+    pub resource_type: ResourceType,
+
+    /// Delivery type of the asset
+    #[arg(long = "type", value_enum, value_name = "TYPE", default_value = "upload")]
+    pub delivery_type: DeliveryType,
+
+    /// Transformation applied on delivery (e.g. "w_800,c_fill/f_auto")
+    #[arg(short, long, value_name = "TRANSFORMATION", value_parser = parse_transformation)]
+    pub transform: Option<Transformation>,
+
+    /// Asset version to include in the URL
+    #[arg(long, value_name = "VERSION")]
+    pub version: Option<u64>,
+
+    /// Sign the URL with the API secret (always done for private and authenticated assets)
+    #[arg(long)]
+    pub sign: bool,
+
+    /// Add an authentication token that expires after this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub expires_in: Option<u64>,
+
+    /// Path pattern the token grants access to (e.g. "/image/authenticated/*")
+    #[arg(long, value_name = "ACL", requires = "expires_in")]
+    pub acl: Option<String>,
+}
+
+pub fn execute(args: UrlArgs) -> Result<()> {
+    let config = Config::load_or_env()?;
+
+    let mut url = DeliveryUrl::new(&config.cloudinary, &args.public_id)
+        .resource_type(args.resource_type.as_str())
+        .delivery_type(args.delivery_type)
+        .transformation(args.transform.as_ref())
+        .version(args.version);
+    if args.sign {
+        url = url.signed(true);
+    }
+
+    if let Some(expires_in) = args.expires_in {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("Failed to get system time")?
+            .as_secs();
+        url = url.token(AuthToken {
+            expiration: now + expires_in,
+            acl: args.acl,
+        });
+    }
+
+    println!("{}", url.build()?);
+
+    Ok(())
+}
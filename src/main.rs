@@ -22,42 +22,81 @@ enum Commands {
     Init,
     
     /// Upload files to Cloudinary
-    Upload {
-        /// Path to a file or directory to upload (optional, defaults to interactive selection)
-        #[arg(value_name = "PATH")]
-        path: Option<String>,
-        
-        /// Specify Cloudinary destination folder
-        #[arg(short, long, value_name = "FOLDER")]
-        folder: Option<String>,
-        
-        /// Apply transformation (e.g., webp, avif)
-        #[arg(short, long, value_name = "FORMAT")]
-        transform: Option<String>,
-        
-        /// Save URLs to a specified file
-        #[arg(short, long, value_name = "FILE")]
-        output: Option<String>,
-    },
+    Upload(Box<commands::upload::UploadArgs>),
     
     /// View or edit current configuration
     Config,
+
+    /// List assets in the account
+    List(commands::list::ListArgs),
+
+    /// Delete assets by public ID, prefix or tag
+    Delete(commands::delete::DeleteArgs),
+
+    /// Rename an asset, or move every asset under a prefix
+    Mv(commands::mv::MvArgs),
+
+    /// Show details of an asset
+    Info(commands::info::InfoArgs),
+
+    /// Add, remove, replace or clear tags on existing assets
+    Tag {
+        #[command(subcommand)]
+        command: commands::tag::TagCommand,
+    },
+
+    /// List, create and delete remote folders
+    Folders {
+        #[command(subcommand)]
+        command: commands::folders::FoldersCommand,
+    },
+
+    /// Build a delivery URL, optionally signed or with an authentication token
+    Url(commands::url::UrlArgs),
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     let cli = Cli::parse();
 
-    match &cli.command {
+    if let Err(err) = run(cli).await {
+        utils::print_error(&format!("{:#}", err));
+        std::process::exit(cloudinary::error::exit_code(&err));
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    match cli.command {
         Commands::Init => {
             commands::init::execute()?;
         }
-        Commands::Upload { path, folder, transform, output } => {
-            commands::upload::execute(path.clone(), folder.clone(), transform.clone(), output.clone()).await?;
+        Commands::Upload(args) => {
+            commands::upload::execute(*args).await?;
         }
         Commands::Config => {
             commands::config::execute()?;
         }
+        Commands::List(args) => {
+            commands::list::execute(args).await?;
+        }
+        Commands::Delete(args) => {
+            commands::delete::execute(args).await?;
+        }
+        Commands::Mv(args) => {
+            commands::mv::execute(args).await?;
+        }
+        Commands::Info(args) => {
+            commands::info::execute(args).await?;
+        }
+        Commands::Tag { command } => {
+            commands::tag::execute(command).await?;
+        }
+        Commands::Folders { command } => {
+            commands::folders::execute(command).await?;
+        }
+        Commands::Url(args) => {
+            commands::url::execute(args)?;
+        }
     }
 
     Ok(())
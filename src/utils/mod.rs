@@ -2,6 +2,7 @@ pub mod fzf;
 
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
+use std::io::{self, Read, Write};
 use std::path::Path;
 
 pub fn create_progress_bar(len: u64) -> ProgressBar {
@@ -31,9 +32,61 @@ pub fn print_warning(message: &str) {
     println!("{} {}", "⚠".yellow().bold(), message);
 }
 
+/// Human readable size such as `1.5 MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no
+pub fn confirm(question: &str) -> anyhow::Result<bool> {
+    print!("{} [y/N]: ", question);
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+/// Read public IDs, one per line, from a file or from stdin when `path` is `-`.
+///
+/// Blank lines and lines starting with `#` are skipped.
+pub fn read_public_ids(path: &str) -> anyhow::Result<Vec<String>> {
+    use anyhow::Context;
+
+    let contents = if path == "-" {
+        let mut contents = String::new();
+        io::stdin()
+            .read_to_string(&mut contents)
+            .context("Failed to read public IDs from stdin")?;
+        contents
+    } else {
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read public IDs from {}", path))?
+    };
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect())
+}
+
 pub fn save_urls_to_file(urls: &[String], output_file: &Path) -> anyhow::Result<()> {
     use std::fs::File;
-    use std::io::Write;
     
     let mut file = File::create(output_file)?;
     for url in urls {